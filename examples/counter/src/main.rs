@@ -1,5 +1,3 @@
-use std::str::FromStr;
-
 use arwa::html::{custom_element_name, GenericExtendableElement};
 use arwa::spawn_local;
 use arwa::window::window;
@@ -9,9 +7,9 @@ use guise::view_model::ViewModel;
 use guise::{Listener, VDom};
 
 #[derive(guise::Attributes, Clone, Default)]
+#[attributes(rename_all = "kebab-case")]
 struct CounterAttributes {
-    #[attribute_name = "initial-count"]
-    initial_count: Option<String>,
+    initial_count: u32,
 }
 
 fn main() {
@@ -20,23 +18,20 @@ fn main() {
     guise::register::<GenericExtendableElement, CounterAttributes, _, _>(
         &registry,
         &custom_element_name!("x-counter"),
-        |_, mut attribute_changes| {
+        |_, attribute_changes| {
             let view_model = ViewModel::new(0u32);
 
             spawn_local({
                 let updater = view_model.updater();
+                let mut attribute_diffs = attribute_changes.diffs();
 
                 async move {
-                    while let Some(change) = attribute_changes.next().await {
-                        updater
-                            .update(|count| {
-                                *count = change
-                                    .initial_count
-                                    .as_ref()
-                                    .and_then(|c| u32::from_str(c).ok())
-                                    .unwrap_or(0)
-                            })
-                            .unwrap();
+                    while let Some(diff) = attribute_diffs.next().await {
+                        if diff.initial_count {
+                            let initial_count = attribute_diffs.attributes().initial_count;
+
+                            updater.update(|count| *count = initial_count).unwrap();
+                        }
                     }
                 }
             });
@@ -6,13 +6,15 @@ mod todo_item;
 
 use arwa::html::custom_element_name;
 use arwa::window::window;
+use guise::RegisterOptions;
 
 fn main() {
     let registry = window().custom_elements();
 
-    guise::register(
+    guise::register_with_props(
         &registry,
         &custom_element_name!("todo-item"),
+        RegisterOptions::default(),
         todo_item::init,
     );
     guise::register(&registry, &custom_element_name!("todo-app"), todo_app::init);
@@ -7,7 +7,7 @@ use atomic_counter::AtomicCounter;
 use futures::{Stream, StreamExt};
 use guise::vdom_builder_ext::*;
 use guise::view_model::ViewModel;
-use guise::{AttributesChanged, Listener, VDom};
+use guise::{AttributesChanged, Host, Listener, VDom};
 use viemo::memo::OwnedMemo;
 use viemo::versioned_cell::VersionedCell;
 use viemo::watcher::Watcher3;
@@ -61,7 +61,10 @@ impl Component {
     }
 }
 
-pub fn init(_: &GenericExtendableElement, _: AttributesChanged<()>) -> impl Stream<Item = VDom> {
+pub fn init(
+    _: &Host<GenericExtendableElement>,
+    _: AttributesChanged<()>,
+) -> impl Stream<Item = VDom> {
     let all_todo_ids = OwnedMemo::new(&APP_DATA, |app, cx| {
         app.todos.deref(cx).keys().copied().collect::<Vec<usize>>()
     });
@@ -226,9 +229,7 @@ pub fn init(_: &GenericExtendableElement, _: AttributesChanged<()>) -> impl Stre
                         e.attr_id("toggle-all");
                         e.attr_class("toggle-all");
 
-                        if component.all_completed() {
-                            e.attr_checked();
-                        }
+                        e.controlled_checked(component.all_completed());
 
                         e.sink_input(check_toggle_all_listener.clone());
                     });
@@ -247,7 +248,8 @@ pub fn init(_: &GenericExtendableElement, _: AttributesChanged<()>) -> impl Stre
                                 name!("li"),
                                 custom_element_name!("todo-item"),
                                 |mut e| {
-                                    e.attr(name!("todo-id"), &id.to_string());
+                                    e.key(&id.to_string());
+                                    e.props(*id);
                                 },
                             );
                         }
@@ -1,5 +1,3 @@
-use std::str::FromStr;
-
 use arwa::dom::SelectionDirection;
 use arwa::event::Event;
 use arwa::html::{HtmlElement, HtmlInputElement, HtmlLiElement};
@@ -9,18 +7,12 @@ use futures::{Stream, StreamExt as BaseStreamExt};
 use guise::flatten_abridged::StreamExt as FlattenAbridgedStreamExt;
 use guise::vdom_builder_ext::*;
 use guise::view_model::ViewModel;
-use guise::{AttributesChanged, ElementRef, Listener, VDom};
+use guise::{AttributesChanged, ElementRef, Host, Listener, PropsChanged, VDom};
 use viemo::memo::OptionCellMemo;
 use viemo::watcher::Watcher;
 
 use crate::model::APP_DATA;
 
-#[derive(guise::Attributes, Clone, Default)]
-pub struct Attributes {
-    #[attribute_name = "todo-id"]
-    id: Option<String>,
-}
-
 #[derive(Default)]
 struct Component {
     note: String,
@@ -29,12 +21,12 @@ struct Component {
 }
 
 pub fn init(
-    _: &HtmlLiElement,
-    attribute_changes: AttributesChanged<Attributes>,
+    _: &Host<HtmlLiElement>,
+    _: AttributesChanged<()>,
+    props_changes: PropsChanged<usize>,
 ) -> impl Stream<Item = VDom> {
-    attribute_changes.flat_map_abridged(|attributes| {
-        let id =
-            usize::from_str(attributes.id.as_deref().unwrap_or_default()).expect("invalid index");
+    props_changes.flat_map_abridged(|id| {
+        let id = *id;
         let view_model = ViewModel::new(Component::default());
         let edit_ref = ElementRef::new();
 
@@ -157,7 +149,7 @@ pub fn init(
                         e.attr_type("text");
                         e.attr_class("edit");
                         e.attr_autofocus();
-                        e.attr_value(&component.note);
+                        e.controlled_value(&component.note);
 
                         e.sink_key_down(save_enter_listener.clone());
                         e.sink_focus_out(save_blur_listener.clone());
@@ -176,9 +168,7 @@ pub fn init(
                             e.attr_type("checkbox");
                             e.attr_class("toggle");
 
-                            if component.complete {
-                                e.attr_checked();
-                            }
+                            e.controlled_checked(component.complete);
 
                             e.sink_input(check_complete_listener.clone());
                         });
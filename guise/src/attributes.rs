@@ -1,25 +1,254 @@
+use std::fmt;
+use std::str::FromStr;
+
 use arwa::dom::Name;
 
+use crate::util::warn;
+
 pub trait Attributes: Clone + Default {
     const OBSERVED: &'static [Name];
 
-    fn update(&mut self, name: &Name, value: Option<String>);
+    /// Records which attributes changed (see [AttributesChanged::diffs]).
+    ///
+    /// `#[derive(Attributes)]` generates a companion struct named after the attributes struct
+    /// with a `Diff` suffix (or the name given with `#[attributes(diff = "...")]`), with a `bool`
+    /// field for every attribute field.
+    ///
+    /// [AttributesChanged::diffs]: crate::AttributesChanged::diffs
+    type Diff: Clone + Default;
+
+    /// Updates the field for the attribute with the given `name` to the new `value` (`None` if the
+    /// attribute was removed).
+    ///
+    /// If the `value` cannot be parsed, the field is reset to its default value and an error is
+    /// returned.
+    fn update(&mut self, name: &Name, value: Option<String>) -> Result<(), AttributeError>;
+
+    /// Marks the field for the attribute with the given `name` as changed in the `diff`.
+    fn record_change(diff: &mut Self::Diff, name: &Name);
 }
 
 impl Attributes for () {
     const OBSERVED: &'static [Name] = &[];
 
-    fn update(&mut self, _name: &Name, _value: Option<String>) {
-        ()
+    type Diff = ();
+
+    fn update(&mut self, _name: &Name, _value: Option<String>) -> Result<(), AttributeError> {
+        Ok(())
+    }
+
+    fn record_change(_diff: &mut (), _name: &Name) {}
+}
+
+/// Returns the attributes of an element on which none of the observed attributes are present.
+pub(crate) fn initial_attributes<A>() -> A
+where
+    A: Attributes,
+{
+    let mut attributes = A::default();
+
+    for name in A::OBSERVED {
+        // Absent attributes never fail to parse.
+        let _ = attributes.update(name, None);
     }
+
+    attributes
+}
+
+pub(crate) fn warn_attribute_error(error: &AttributeError) {
+    warn(&error.to_string());
+}
+
+/// A type that can represent the value of an attribute.
+pub trait Attribute: Sized {
+    /// Parses the `value` of the attribute (`None` if the attribute is absent).
+    ///
+    /// Returns `Ok(None)` if the field should be set to its default value.
+    fn parse(value: Option<&str>) -> Result<Option<Self>, AttributeParseError>;
 }
 
-pub trait Attribute {
-    fn update(&mut self, value: Option<String>);
+/// A present attribute is parsed with `T`'s [FromStr] implementation; an absent attribute is the
+/// default value (`None`, unless the field specifies `#[attribute(default = ...)]`).
+impl<T> Attribute for Option<T>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    fn parse(value: Option<&str>) -> Result<Option<Self>, AttributeParseError> {
+        match value {
+            Some(value) => T::from_str(value)
+                .map(|value| Some(Some(value)))
+                .map_err(AttributeParseError::new),
+            None => Ok(None),
+        }
+    }
 }
 
-impl Attribute for Option<String> {
-    fn update(&mut self, value: Option<String>) {
-        *self = value;
+/// Boolean attributes use presence semantics: an attribute that is present is `true`, regardless
+/// of its value; an absent attribute is the default value (`false`, unless the field specifies
+/// `#[attribute(default = ...)]`).
+impl Attribute for bool {
+    fn parse(value: Option<&str>) -> Result<Option<Self>, AttributeParseError> {
+        Ok(value.map(|_| true))
+    }
+}
+
+impl Attribute for String {
+    fn parse(value: Option<&str>) -> Result<Option<Self>, AttributeParseError> {
+        Ok(value.map(|value| value.to_string()))
+    }
+}
+
+macro_rules! numeric_attribute {
+    ($($ty:ident),*) => {
+        $(
+            /// Leading and trailing whitespace is ignored; an absent attribute is the default
+            /// value.
+            impl Attribute for $ty {
+                fn parse(value: Option<&str>) -> Result<Option<Self>, AttributeParseError> {
+                    value
+                        .map(|value| value.trim().parse().map_err(AttributeParseError::new))
+                        .transpose()
+                }
+            }
+        )*
+    };
+}
+
+numeric_attribute!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+/// An error produced when parsing the value of an attribute.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AttributeParseError {
+    message: String,
+}
+
+impl AttributeParseError {
+    pub fn new<T>(message: T) -> Self
+    where
+        T: fmt::Display,
+    {
+        AttributeParseError {
+            message: message.to_string(),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for AttributeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Describes an attribute value that could not be parsed (see [Attributes::update]).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AttributeError {
+    attribute_name: String,
+    value: String,
+    error: AttributeParseError,
+}
+
+impl AttributeError {
+    pub fn new(attribute_name: &Name, value: String, error: AttributeParseError) -> Self {
+        let attribute_name: &str = attribute_name.as_ref();
+
+        AttributeError {
+            attribute_name: attribute_name.to_string(),
+            value,
+            error,
+        }
+    }
+
+    /// The name of the attribute.
+    pub fn attribute_name(&self) -> &str {
+        &self.attribute_name
+    }
+
+    /// The value that could not be parsed.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn error(&self) -> &AttributeParseError {
+        &self.error
+    }
+}
+
+impl fmt::Display for AttributeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid value {:?} for attribute `{}`: {}",
+            self.value, self.attribute_name, self.error
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arwa::dom::name;
+
+    use super::*;
+
+    #[test]
+    fn option_attribute() {
+        assert_eq!(Option::<u32>::parse(Some("1")), Ok(Some(Some(1))));
+        assert_eq!(Option::<u32>::parse(None), Ok(None));
+        assert_eq!(
+            Option::<u32>::parse(Some("a")),
+            Err(AttributeParseError::new("invalid digit found in string"))
+        );
+    }
+
+    #[test]
+    fn bool_attribute() {
+        assert_eq!(bool::parse(Some("")), Ok(Some(true)));
+        assert_eq!(bool::parse(Some("false")), Ok(Some(true)));
+        assert_eq!(bool::parse(None), Ok(None));
+    }
+
+    #[test]
+    fn string_attribute() {
+        assert_eq!(String::parse(Some(" a ")), Ok(Some(" a ".to_string())));
+        assert_eq!(String::parse(Some("")), Ok(Some(String::new())));
+        assert_eq!(String::parse(None), Ok(None));
+    }
+
+    #[test]
+    fn numeric_attribute() {
+        assert_eq!(i32::parse(Some("-1")), Ok(Some(-1)));
+        assert_eq!(u8::parse(Some(" 1\n")), Ok(Some(1)));
+        assert_eq!(f64::parse(Some("\t1.5 ")), Ok(Some(1.5)));
+        assert_eq!(u8::parse(None), Ok(None));
+        assert_eq!(
+            u8::parse(Some("256")),
+            Err(AttributeParseError::new(
+                "number too large to fit in target type"
+            ))
+        );
+        assert_eq!(
+            u8::parse(Some("")),
+            Err(AttributeParseError::new(
+                "cannot parse integer from empty string"
+            ))
+        );
+    }
+
+    #[test]
+    fn attribute_error_message() {
+        let error = AttributeError::new(
+            &name!("count"),
+            "a".to_string(),
+            AttributeParseError::new("invalid digit found in string"),
+        );
+
+        assert_eq!(
+            error.to_string(),
+            "invalid value \"a\" for attribute `count`: invalid digit found in string"
+        );
     }
 }
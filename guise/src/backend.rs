@@ -0,0 +1,117 @@
+//! Abstraction over the DOM that the reconciler patches.
+//!
+//! The reconciler (see [patch_dom]) only ever talks to the DOM through the [Backend] trait. In a
+//! browser, Guise uses the [ArwaBackend]; the [MemoryBackend] maintains a plain Rust node tree
+//! instead, which allows the reconciler to run (and be tested) outside of a browser.
+//!
+//! [patch_dom]: crate::patch_dom
+
+mod arwa_backend;
+mod memory_backend;
+
+pub use self::arwa_backend::{ArwaBackend, ArwaNode};
+pub use self::memory_backend::{MemoryBackend, MemoryNode};
+
+pub(crate) use self::arwa_backend::props_slot;
+
+use std::any::Any;
+use std::rc::Rc;
+
+use arwa::dom::{DynamicElement, Name};
+use arwa::html::CustomElementName;
+
+use crate::vdom::{Namespace, PropertyValue};
+
+pub trait Backend {
+    /// A handle to a node in the DOM tree.
+    ///
+    /// Cloning a node handle should produce a new handle to the same node, not a copy of the node.
+    type Node: Clone;
+
+    /// Creates a new detached element node in the given `namespace`.
+    fn create_element(
+        &self,
+        namespace: Namespace,
+        tag_name: &Name,
+        is: Option<&CustomElementName>,
+    ) -> Self::Node;
+
+    /// Creates a new detached text node.
+    fn create_text(&self, text: &str) -> Self::Node;
+
+    /// Replaces the contents of a text `node` with `text`.
+    fn set_text(&self, node: &Self::Node, text: &str);
+
+    /// Sets the attribute with the given `name` on the `element`, replacing the current value if
+    /// the attribute is already present.
+    ///
+    /// For a namespaced attribute, the `name` is the qualified name of the attribute (e.g.
+    /// `xlink:href`).
+    fn set_attribute(
+        &self,
+        element: &Self::Node,
+        namespace: Option<Namespace>,
+        name: &Name,
+        value: &str,
+    );
+
+    /// Removes the attribute with the given (qualified) `name` from the `element`, if present.
+    fn remove_attribute(&self, element: &Self::Node, name: &str);
+
+    /// Sets the DOM property with the given `name` on the `element`.
+    fn set_property(&self, element: &Self::Node, name: &str, value: PropertyValue);
+
+    /// Returns `true` if the current value of the DOM property with the given `name` on the
+    /// `element` equals `value`.
+    ///
+    /// Values are compared with `Object.is` semantics, so that a `NaN` property value equals a
+    /// `NaN` value (and is not set again on every patch).
+    fn property_eq(&self, element: &Self::Node, name: &str, value: PropertyValue) -> bool;
+
+    /// Passes typed `props` to the `element` (see `ElementBuilder::props`).
+    fn set_props(&self, element: &Self::Node, props: Rc<dyn Any>);
+
+    /// Adds a style sheet with the given `css` to the document or shadow root that the backend
+    /// renders into, unless a style sheet with the same `key` was already added to it.
+    fn add_style(&self, key: &str, css: &str);
+
+    /// Returns the current children of the `parent` node, in order.
+    fn child_nodes(&self, parent: &Self::Node) -> Vec<Self::Node>;
+
+    /// Appends the `child` node to the `parent` node's children, detaching it from its current
+    /// parent if it has one.
+    fn append_child(&self, parent: &Self::Node, child: &Self::Node);
+
+    /// Inserts the `child` node into the `parent` node's children, immediately before the
+    /// `reference` node, detaching it from its current parent if it has one.
+    fn insert_before(&self, parent: &Self::Node, child: &Self::Node, reference: &Self::Node);
+
+    /// Replaces the `old` node with the `new` node in the `old` node's parent.
+    fn replace(&self, old: &Self::Node, new: &Self::Node);
+
+    /// Detaches the `node` from its parent.
+    fn remove(&self, node: &Self::Node);
+
+    /// Returns the tag name if the `node` is an element node, or `None` otherwise.
+    fn tag_name(&self, node: &Self::Node) -> Option<String>;
+
+    /// Returns the namespace if the `node` is an element node in one of the known namespaces, or
+    /// `None` otherwise.
+    fn namespace(&self, node: &Self::Node) -> Option<Namespace>;
+
+    /// Returns the text content if the `node` is a text node, or `None` otherwise.
+    fn text(&self, node: &Self::Node) -> Option<String>;
+
+    /// Returns the value of the attribute with the given (qualified) `name` on the `element`, if
+    /// present.
+    fn attribute(&self, element: &Self::Node, name: &Name) -> Option<String>;
+
+    /// Returns the (qualified) names and the values of all attributes on the `element`, in order.
+    fn attributes(&self, element: &Self::Node) -> Vec<(String, String)>;
+
+    /// Returns the browser DOM element for the `node`, if the backend is backed by a browser DOM.
+    ///
+    /// Sink tasks and [ElementRef](crate::ElementRef)s are only attached to elements for which this
+    /// returns an element.
+    fn dom_element(&self, node: &Self::Node) -> Option<DynamicElement>;
+}
@@ -0,0 +1,520 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::rc::{Rc, Weak};
+
+use arwa::collection::Sequence;
+use arwa::dom::{
+    name, CharacterData, ChildNode, Document, DynamicChildNode, DynamicElement, Element, Name,
+    ParentNode, ShadowRoot, Text,
+};
+use arwa::html::{CustomElementName, HtmlDocument};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::backend::Backend;
+use crate::props::PropsSlot;
+use crate::vdom::{Namespace, PropertyValue};
+
+// Arwa does not expose the namespace-aware DOM methods or generic property access, so we bind
+// these directly.
+#[wasm_bindgen]
+extern "C" {
+    type JsDocument;
+
+    #[wasm_bindgen(method, js_name = createElementNS)]
+    fn create_element_ns(this: &JsDocument, namespace: &str, qualified_name: &str) -> JsValue;
+
+    type JsElement;
+
+    #[wasm_bindgen(method, js_name = setAttributeNS)]
+    fn set_attribute_ns(this: &JsElement, namespace: &str, qualified_name: &str, value: &str);
+
+    #[wasm_bindgen(method, js_name = removeAttribute)]
+    fn remove_attribute(this: &JsElement, qualified_name: &str);
+
+    #[wasm_bindgen(method, js_name = getAttribute)]
+    fn get_attribute(this: &JsElement, qualified_name: &str) -> Option<String>;
+
+    #[wasm_bindgen(method, getter, js_name = namespaceURI)]
+    fn namespace_uri(this: &JsElement) -> Option<String>;
+
+    #[wasm_bindgen(method, js_name = getAttributeNames)]
+    fn get_attribute_names(this: &JsElement) -> Box<[JsValue]>;
+
+    #[wasm_bindgen(method, js_name = getRootNode)]
+    fn get_root_node(this: &JsElement) -> JsValue;
+
+    #[wasm_bindgen(method, js_name = appendChild)]
+    fn append_child(this: &JsElement, child: &JsValue) -> JsValue;
+
+    #[wasm_bindgen(js_namespace = Reflect, js_name = get)]
+    fn reflect_get(target: &JsValue, key: &str) -> JsValue;
+
+    #[wasm_bindgen(js_namespace = Object, js_name = is)]
+    fn object_is(a: &JsValue, b: &JsValue) -> bool;
+
+    #[wasm_bindgen(js_namespace = Reflect, js_name = set)]
+    fn reflect_set(target: &JsValue, key: &str, value: &JsValue) -> bool;
+}
+
+/// The name of the JS property that holds the ID of an element's props slot.
+const PROPS_SLOT_ID_KEY: &str = "__guisePropsSlotId";
+
+enum PropsSlotRef {
+    /// A slot created by a parent for an element that is not (yet) a Guise element; kept alive
+    /// until the element claims it.
+    Unclaimed(Rc<RefCell<PropsSlot>>),
+    /// A slot owned by a Guise element.
+    Claimed(Weak<RefCell<PropsSlot>>),
+}
+
+#[derive(Default)]
+struct PropsSlots {
+    next_id: u32,
+    slots: HashMap<u32, PropsSlotRef>,
+}
+
+// A slot is only reachable through the ID stored on its element, so we drop a slot's entry when
+// its element is garbage collected.
+#[wasm_bindgen(inline_js = r#"
+let registry = null;
+
+export function release_with(element, id, release) {
+    if (!registry) {
+        registry = new FinalizationRegistry(release);
+    }
+
+    registry.register(element, id);
+}
+"#)]
+extern "C" {
+    fn release_with(element: &JsValue, id: u32, release: &JsValue);
+}
+
+thread_local! {
+    static PROPS_SLOTS: RefCell<PropsSlots> = RefCell::new(PropsSlots::default());
+
+    static RELEASE_PROPS_SLOT: Closure<dyn Fn(u32)> = Closure::new(|id: u32| {
+        PROPS_SLOTS.with(|slots| slots.borrow_mut().slots.remove(&id));
+    });
+}
+
+/// Returns the props slot for the `element`, creating it if the element does not have one yet.
+///
+/// If `claim` is `true`, the caller takes ownership of the slot: the slot is dropped when the
+/// caller drops it. Otherwise, the slot is dropped when the element is garbage collected, unless
+/// the element claims it first.
+pub(crate) fn props_slot(element: &JsValue, claim: bool) -> Rc<RefCell<PropsSlot>> {
+    PROPS_SLOTS.with(|slots| {
+        let mut slots = slots.borrow_mut();
+        let id = reflect_get(element, PROPS_SLOT_ID_KEY)
+            .as_f64()
+            .map(|id| id as u32);
+
+        let existing = id.and_then(|id| match slots.slots.get(&id)? {
+            PropsSlotRef::Unclaimed(slot) => Some(slot.clone()),
+            PropsSlotRef::Claimed(slot) => slot.upgrade(),
+        });
+
+        if let (Some(id), Some(slot)) = (id, &existing) {
+            if claim {
+                slots
+                    .slots
+                    .insert(id, PropsSlotRef::Claimed(Rc::downgrade(slot)));
+            }
+
+            return slot.clone();
+        }
+
+        // Drop the entries of slots that no longer exist. Note that we never reuse IDs, as an
+        // element may outlive its slot.
+        slots.slots.retain(|_, slot| match slot {
+            PropsSlotRef::Unclaimed(_) => true,
+            PropsSlotRef::Claimed(slot) => slot.strong_count() > 0,
+        });
+
+        let slot = Rc::new(RefCell::new(PropsSlot::new()));
+        let id = slots.next_id;
+
+        slots.next_id += 1;
+
+        let slot_ref = if claim {
+            PropsSlotRef::Claimed(Rc::downgrade(&slot))
+        } else {
+            PropsSlotRef::Unclaimed(slot.clone())
+        };
+
+        slots.slots.insert(id, slot_ref);
+        reflect_set(element, PROPS_SLOT_ID_KEY, &JsValue::from(id));
+        RELEASE_PROPS_SLOT.with(|release| release_with(element, id, release.as_ref()));
+
+        slot
+    })
+}
+
+/// A node handle for the [ArwaBackend].
+#[derive(Clone)]
+pub enum ArwaNode {
+    Element(DynamicElement),
+    Text(Text),
+    ShadowRoot(ShadowRoot),
+    Other(DynamicChildNode),
+}
+
+impl ArwaNode {
+    fn from_child_node(node: DynamicChildNode) -> Self {
+        match DynamicElement::try_from(node) {
+            Ok(element) => ArwaNode::Element(element),
+            Err(node) => match Text::try_from(node) {
+                Ok(text) => ArwaNode::Text(text),
+                Err(node) => ArwaNode::Other(node),
+            },
+        }
+    }
+
+    fn child_node(&self) -> DynamicChildNode {
+        match self {
+            ArwaNode::Element(element) => element.clone().into(),
+            ArwaNode::Text(text) => text.clone().into(),
+            ArwaNode::Other(node) => node.clone(),
+            ArwaNode::ShadowRoot(_) => panic!("a shadow root is not a child node"),
+        }
+    }
+
+    fn element(&self) -> &DynamicElement {
+        if let ArwaNode::Element(element) = self {
+            element
+        } else {
+            panic!("actual node type does not match v-node type")
+        }
+    }
+}
+
+impl From<DynamicElement> for ArwaNode {
+    fn from(element: DynamicElement) -> Self {
+        ArwaNode::Element(element)
+    }
+}
+
+impl From<ShadowRoot> for ArwaNode {
+    fn from(shadow_root: ShadowRoot) -> Self {
+        ArwaNode::ShadowRoot(shadow_root)
+    }
+}
+
+/// The default [Backend], which patches the browser DOM through Arwa.
+#[derive(Clone)]
+pub struct ArwaBackend {
+    document: HtmlDocument,
+    /// The node whose root (see `getRootNode`) receives the styles; the document if `None`.
+    style_container: Option<JsValue>,
+    /// The keys of the styles that were already added, so that only the first patch that uses a
+    /// style has to look for it in the root.
+    added_styles: Rc<RefCell<HashSet<String>>>,
+}
+
+impl ArwaBackend {
+    pub fn new(document: HtmlDocument) -> Self {
+        ArwaBackend {
+            document,
+            style_container: None,
+            added_styles: Rc::new(RefCell::new(HashSet::new())),
+        }
+    }
+
+    /// Creates a backend for patching the children of the `container`; styles are added to the
+    /// container's root, which is either the document or a shadow root.
+    pub fn for_container(document: HtmlDocument, container: &ArwaNode) -> Self {
+        let container: &JsValue = match container {
+            ArwaNode::Element(element) => element.as_ref(),
+            ArwaNode::ShadowRoot(shadow_root) => shadow_root.as_ref(),
+            ArwaNode::Text(text) => text.as_ref(),
+            ArwaNode::Other(node) => node.as_ref(),
+        };
+
+        ArwaBackend {
+            style_container: Some(container.clone()),
+            ..ArwaBackend::new(document)
+        }
+    }
+
+    pub fn document(&self) -> &HtmlDocument {
+        &self.document
+    }
+}
+
+impl Backend for ArwaBackend {
+    type Node = ArwaNode;
+
+    fn create_element(
+        &self,
+        namespace: Namespace,
+        tag_name: &Name,
+        is: Option<&CustomElementName>,
+    ) -> ArwaNode {
+        let element = if namespace != Namespace::Html {
+            let document: &JsValue = self.document.as_ref();
+
+            document
+                .unchecked_ref::<JsDocument>()
+                .create_element_ns(namespace.uri(), tag_name.as_ref())
+                .unchecked_into()
+        } else if let Some(is) = is {
+            self.document.create_customized_element(tag_name, is)
+        } else {
+            self.document.create_element(tag_name)
+        };
+
+        ArwaNode::Element(element)
+    }
+
+    fn create_text(&self, text: &str) -> ArwaNode {
+        ArwaNode::Text(self.document.create_text(text))
+    }
+
+    fn set_text(&self, node: &ArwaNode, text: &str) {
+        if let ArwaNode::Text(node) = node {
+            node.set_data(text);
+        } else {
+            panic!("actual node type does not match v-node type")
+        }
+    }
+
+    fn set_attribute(
+        &self,
+        element: &ArwaNode,
+        namespace: Option<Namespace>,
+        name: &Name,
+        value: &str,
+    ) {
+        let element = element.element();
+
+        if let Some(namespace) = namespace {
+            let js_element: &JsValue = element.as_ref();
+
+            js_element.unchecked_ref::<JsElement>().set_attribute_ns(
+                namespace.uri(),
+                name.as_ref(),
+                value,
+            );
+
+            return;
+        }
+
+        element.attributes().set(name, value);
+    }
+
+    fn remove_attribute(&self, element: &ArwaNode, name: &str) {
+        let js_element: &JsValue = element.element().as_ref();
+
+        js_element
+            .unchecked_ref::<JsElement>()
+            .remove_attribute(name);
+    }
+
+    fn set_property(&self, element: &ArwaNode, name: &str, value: PropertyValue) {
+        let value = match value {
+            PropertyValue::Bool(value) => JsValue::from(value),
+            PropertyValue::Number(value) => JsValue::from(value),
+            PropertyValue::String(value) => JsValue::from(value),
+        };
+
+        let is_text_control = matches!(element.element().tag_name().as_str(), "INPUT" | "TEXTAREA");
+        let element: &JsValue = element.element().as_ref();
+
+        if name == "value" && is_text_control {
+            set_value_preserving_selection(element, &value);
+        } else {
+            reflect_set(element, name, &value);
+        }
+    }
+
+    fn property_eq(&self, element: &ArwaNode, name: &str, value: PropertyValue) -> bool {
+        let element: &JsValue = element.element().as_ref();
+        let current = reflect_get(element, name);
+        let value = match value {
+            PropertyValue::Bool(value) => JsValue::from(value),
+            PropertyValue::Number(value) => JsValue::from(value),
+            PropertyValue::String(value) => JsValue::from(value),
+        };
+
+        object_is(&current, &value)
+    }
+
+    fn set_props(&self, element: &ArwaNode, props: Rc<dyn Any>) {
+        let element: &JsValue = element.element().as_ref();
+
+        props_slot(element, false).borrow_mut().set(props);
+    }
+
+    fn add_style(&self, key: &str, css: &str) {
+        if self.added_styles.borrow().contains(key) {
+            return;
+        }
+
+        self.added_styles.borrow_mut().insert(key.to_string());
+
+        let document: &JsValue = self.document.as_ref();
+        let root = self
+            .style_container
+            .as_ref()
+            .map(|container| container.unchecked_ref::<JsElement>().get_root_node())
+            .unwrap_or_else(|| document.clone());
+
+        // Other backends (e.g. of other components) may have added the style to the root already.
+        let marker = format!("__guiseStyle_{}", key);
+
+        if reflect_get(&root, &marker).is_truthy() {
+            return;
+        }
+
+        reflect_set(&root, &marker, &JsValue::TRUE);
+
+        let style = self.document.create_element(&name!("style"));
+        let style: &JsValue = style.as_ref();
+
+        reflect_set(style, "textContent", &JsValue::from(css));
+
+        // A shadow root receives the style itself; a document receives it in its `<head>`.
+        let parent = if &root == document {
+            reflect_get(document, "head")
+        } else {
+            root
+        };
+
+        parent.unchecked_ref::<JsElement>().append_child(style);
+    }
+
+    fn child_nodes(&self, parent: &ArwaNode) -> Vec<ArwaNode> {
+        let children = match parent {
+            ArwaNode::Element(element) => element.child_nodes(),
+            ArwaNode::ShadowRoot(shadow_root) => shadow_root.child_nodes(),
+            _ => return Vec::new(),
+        };
+
+        (0..children.len())
+            .filter_map(|i| children.get(i))
+            .map(ArwaNode::from_child_node)
+            .collect()
+    }
+
+    fn append_child(&self, parent: &ArwaNode, child: &ArwaNode) {
+        let child = child.child_node();
+
+        match parent {
+            ArwaNode::Element(element) => element.append_child(&child),
+            ArwaNode::ShadowRoot(shadow_root) => shadow_root.append_child(&child),
+            _ => panic!("node cannot have children"),
+        }
+    }
+
+    fn insert_before(&self, _parent: &ArwaNode, child: &ArwaNode, reference: &ArwaNode) {
+        reference
+            .child_node()
+            .before_insert_node(&child.child_node());
+    }
+
+    fn replace(&self, old: &ArwaNode, new: &ArwaNode) {
+        old.child_node().replace_with(&new.child_node());
+    }
+
+    fn remove(&self, node: &ArwaNode) {
+        node.child_node().disconnect();
+    }
+
+    fn tag_name(&self, node: &ArwaNode) -> Option<String> {
+        if let ArwaNode::Element(element) = node {
+            Some(element.tag_name())
+        } else {
+            None
+        }
+    }
+
+    fn namespace(&self, node: &ArwaNode) -> Option<Namespace> {
+        if let ArwaNode::Element(element) = node {
+            let js_element: &JsValue = element.as_ref();
+
+            js_element
+                .unchecked_ref::<JsElement>()
+                .namespace_uri()
+                .and_then(|uri| Namespace::from_uri(&uri))
+        } else {
+            None
+        }
+    }
+
+    fn text(&self, node: &ArwaNode) -> Option<String> {
+        if let ArwaNode::Text(text) = node {
+            Some(text.data())
+        } else {
+            None
+        }
+    }
+
+    fn attribute(&self, element: &ArwaNode, name: &Name) -> Option<String> {
+        element
+            .element()
+            .attributes()
+            .lookup(name)
+            .map(|attribute| attribute.value())
+    }
+
+    fn attributes(&self, element: &ArwaNode) -> Vec<(String, String)> {
+        let js_element: &JsValue = element.element().as_ref();
+        let element = js_element.unchecked_ref::<JsElement>();
+
+        element
+            .get_attribute_names()
+            .iter()
+            .filter_map(|name| name.as_string())
+            .map(|name| {
+                let value = element.get_attribute(&name).unwrap_or_default();
+
+                (name, value)
+            })
+            .collect()
+    }
+
+    fn dom_element(&self, node: &ArwaNode) -> Option<DynamicElement> {
+        if let ArwaNode::Element(element) = node {
+            Some(element.clone())
+        } else {
+            None
+        }
+    }
+}
+
+/// Sets the `value` property of a form control.
+///
+/// Setting the value of a text control moves the caret to the end of the new value; if the control
+/// has focus, we restore the selection afterwards (clamped to the length of the new value), so
+/// that the user can keep typing where they were.
+fn set_value_preserving_selection(element: &JsValue, value: &JsValue) {
+    let root = element.unchecked_ref::<JsElement>().get_root_node();
+    let focused = &reflect_get(&root, "activeElement") == element;
+
+    // Note: `selectionStart` and `selectionEnd` are `null` for controls that don't support text
+    // selection (e.g. checkboxes and `<select>` elements).
+    let selection = if focused {
+        reflect_get(element, "selectionStart")
+            .as_f64()
+            .zip(reflect_get(element, "selectionEnd").as_f64())
+    } else {
+        None
+    };
+
+    reflect_set(element, "value", value);
+
+    if let Some((start, end)) = selection {
+        // Selection offsets are in UTF-16 code units.
+        let len = reflect_get(element, "value")
+            .as_string()
+            .map(|value| value.encode_utf16().count())
+            .unwrap_or(0) as f64;
+
+        reflect_set(element, "selectionStart", &JsValue::from(start.min(len)));
+        reflect_set(element, "selectionEnd", &JsValue::from(end.min(len)));
+    }
+}
@@ -0,0 +1,457 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+use arwa::dom::{DynamicElement, Name};
+use arwa::html::CustomElementName;
+
+use crate::backend::Backend;
+use crate::vdom::{Namespace, PropertyValue};
+
+enum PropertyData {
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+
+impl PropertyData {
+    fn eq(&self, value: PropertyValue) -> bool {
+        match (self, value) {
+            (PropertyData::Bool(a), PropertyValue::Bool(b)) => *a == b,
+            // Numbers are compared like `Object.is` compares them: `NaN` equals `NaN`, but `0`
+            // does not equal `-0`.
+            (PropertyData::Number(a), PropertyValue::Number(b)) => {
+                if a.is_nan() {
+                    b.is_nan()
+                } else {
+                    *a == b && a.is_sign_negative() == b.is_sign_negative()
+                }
+            }
+            (PropertyData::String(a), PropertyValue::String(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+struct AttributeData {
+    namespace: Option<Namespace>,
+    name: String,
+    value: String,
+}
+
+enum NodeKind {
+    Container,
+    Element {
+        namespace: Namespace,
+        tag_name: String,
+        is: Option<String>,
+        attributes: Vec<AttributeData>,
+        properties: Vec<(String, PropertyData)>,
+        props: Option<Rc<dyn Any>>,
+    },
+    Text(String),
+}
+
+struct NodeData {
+    kind: NodeKind,
+    parent: Option<Weak<RefCell<NodeData>>>,
+    children: Vec<MemoryNode>,
+}
+
+/// A node handle for the [MemoryBackend].
+///
+/// Cloning a `MemoryNode` produces a new handle to the same node. Two handles can be checked for
+/// node identity with [ptr_eq](MemoryNode::ptr_eq).
+#[derive(Clone)]
+pub struct MemoryNode {
+    data: Rc<RefCell<NodeData>>,
+}
+
+impl MemoryNode {
+    /// Creates a new container node, to serve as the root into which a [VDom](crate::VDom) is
+    /// patched.
+    pub fn container() -> Self {
+        MemoryNode::new(NodeKind::Container)
+    }
+
+    fn new(kind: NodeKind) -> Self {
+        MemoryNode {
+            data: Rc::new(RefCell::new(NodeData {
+                kind,
+                parent: None,
+                children: Vec::new(),
+            })),
+        }
+    }
+
+    /// Returns `true` if `self` and `other` are handles to the same node.
+    pub fn ptr_eq(&self, other: &MemoryNode) -> bool {
+        Rc::ptr_eq(&self.data, &other.data)
+    }
+
+    /// Returns the tag name if this node is an element node, or `None` otherwise.
+    pub fn tag_name(&self) -> Option<String> {
+        if let NodeKind::Element { tag_name, .. } = &self.data.borrow().kind {
+            Some(tag_name.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the namespace if this node is an element node, or `None` otherwise.
+    pub fn namespace(&self) -> Option<Namespace> {
+        if let NodeKind::Element { namespace, .. } = &self.data.borrow().kind {
+            Some(*namespace)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the customized built-in element name if this node is a customized element, or
+    /// `None` otherwise.
+    pub fn is(&self) -> Option<String> {
+        if let NodeKind::Element { is, .. } = &self.data.borrow().kind {
+            is.clone()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the value of the attribute with the given `name` if this node is an element node
+    /// and the attribute is present, or `None` otherwise.
+    pub fn attribute(&self, name: &str) -> Option<String> {
+        if let NodeKind::Element { attributes, .. } = &self.data.borrow().kind {
+            attributes
+                .iter()
+                .find(|a| a.name == name)
+                .map(|a| a.value.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the namespace of the attribute with the given `name` if this node is an element
+    /// node and the attribute is present and namespaced, or `None` otherwise.
+    pub fn attribute_namespace(&self, name: &str) -> Option<Namespace> {
+        if let NodeKind::Element { attributes, .. } = &self.data.borrow().kind {
+            attributes.iter().find(|a| a.name == name)?.namespace
+        } else {
+            None
+        }
+    }
+
+    /// Returns the attributes of this node in the order in which they were first set, or an
+    /// empty list if this node is not an element node.
+    pub fn attributes(&self) -> Vec<(String, String)> {
+        if let NodeKind::Element { attributes, .. } = &self.data.borrow().kind {
+            attributes
+                .iter()
+                .map(|a| (a.name.clone(), a.value.clone()))
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Returns `true` if this node is an element node on which the property with the given `name`
+    /// has been set to `value`.
+    pub fn property_eq(&self, name: &str, value: PropertyValue) -> bool {
+        if let NodeKind::Element { properties, .. } = &self.data.borrow().kind {
+            properties
+                .iter()
+                .any(|(n, data)| n == name && data.eq(value))
+        } else {
+            false
+        }
+    }
+
+    /// Returns the typed props that were passed to this node if this node is an element node and
+    /// the props are of type `P`, or `None` otherwise.
+    pub fn props<P>(&self) -> Option<Rc<P>>
+    where
+        P: 'static,
+    {
+        if let NodeKind::Element { props, .. } = &self.data.borrow().kind {
+            props.clone()?.downcast().ok()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the text content if this node is a text node, or `None` otherwise.
+    pub fn text(&self) -> Option<String> {
+        if let NodeKind::Text(text) = &self.data.borrow().kind {
+            Some(text.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the children of this node, in order.
+    pub fn children(&self) -> Vec<MemoryNode> {
+        self.data.borrow().children.clone()
+    }
+
+    /// Returns the parent of this node, if it is attached to a parent.
+    pub fn parent(&self) -> Option<MemoryNode> {
+        self.data
+            .borrow()
+            .parent
+            .as_ref()
+            .and_then(|p| p.upgrade())
+            .map(|data| MemoryNode { data })
+    }
+
+    fn detach(&self) {
+        if let Some(parent) = self.parent() {
+            parent
+                .data
+                .borrow_mut()
+                .children
+                .retain(|c| !c.ptr_eq(self));
+        }
+
+        self.data.borrow_mut().parent = None;
+    }
+
+    fn index_in(&self, parent: &MemoryNode) -> usize {
+        parent
+            .data
+            .borrow()
+            .children
+            .iter()
+            .position(|c| c.ptr_eq(self))
+            .expect("node is not a child of the parent node")
+    }
+
+    fn insert(&self, parent: &MemoryNode, index: usize) {
+        self.data.borrow_mut().parent = Some(Rc::downgrade(&parent.data));
+        parent
+            .data
+            .borrow_mut()
+            .children
+            .insert(index, self.clone());
+    }
+}
+
+impl fmt::Debug for MemoryNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let data = self.data.borrow();
+
+        match &data.kind {
+            NodeKind::Container => {
+                for child in data.children.iter() {
+                    fmt::Debug::fmt(child, f)?;
+                }
+
+                Ok(())
+            }
+            NodeKind::Element {
+                tag_name,
+                is,
+                attributes,
+                ..
+            } => {
+                write!(f, "<{}", tag_name)?;
+
+                if let Some(is) = is {
+                    write!(f, " is=\"{}\"", is)?;
+                }
+
+                for attribute in attributes.iter() {
+                    write!(f, " {}=\"{}\"", attribute.name, attribute.value)?;
+                }
+
+                write!(f, ">")?;
+
+                for child in data.children.iter() {
+                    fmt::Debug::fmt(child, f)?;
+                }
+
+                write!(f, "</{}>", tag_name)
+            }
+            NodeKind::Text(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+/// A [Backend] that maintains a plain Rust node tree, rather than a browser DOM.
+///
+/// Sink tasks and [ElementRef](crate::ElementRef)s are never attached to the nodes of a memory
+/// backend.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct MemoryBackend;
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        MemoryBackend
+    }
+}
+
+impl Backend for MemoryBackend {
+    type Node = MemoryNode;
+
+    fn create_element(
+        &self,
+        namespace: Namespace,
+        tag_name: &Name,
+        is: Option<&CustomElementName>,
+    ) -> MemoryNode {
+        let tag_name: &str = tag_name.as_ref();
+        let is = is.map(|is| {
+            let is: &str = is.as_ref();
+
+            is.to_string()
+        });
+
+        MemoryNode::new(NodeKind::Element {
+            namespace,
+            tag_name: tag_name.to_string(),
+            is,
+            attributes: Vec::new(),
+            properties: Vec::new(),
+            props: None,
+        })
+    }
+
+    fn create_text(&self, text: &str) -> MemoryNode {
+        MemoryNode::new(NodeKind::Text(text.to_string()))
+    }
+
+    fn set_text(&self, node: &MemoryNode, text: &str) {
+        if let NodeKind::Text(data) = &mut node.data.borrow_mut().kind {
+            *data = text.to_string();
+        } else {
+            panic!("actual node type does not match v-node type")
+        }
+    }
+
+    fn set_attribute(
+        &self,
+        element: &MemoryNode,
+        namespace: Option<Namespace>,
+        name: &Name,
+        value: &str,
+    ) {
+        let name: &str = name.as_ref();
+
+        if let NodeKind::Element { attributes, .. } = &mut element.data.borrow_mut().kind {
+            if let Some(attribute) = attributes.iter_mut().find(|a| a.name == name) {
+                attribute.namespace = namespace;
+                attribute.value = value.to_string();
+            } else {
+                attributes.push(AttributeData {
+                    namespace,
+                    name: name.to_string(),
+                    value: value.to_string(),
+                });
+            }
+        } else {
+            panic!("actual node type does not match v-node type")
+        }
+    }
+
+    fn remove_attribute(&self, element: &MemoryNode, name: &str) {
+        if let NodeKind::Element { attributes, .. } = &mut element.data.borrow_mut().kind {
+            attributes.retain(|a| a.name != name);
+        } else {
+            panic!("actual node type does not match v-node type")
+        }
+    }
+
+    fn set_property(&self, element: &MemoryNode, name: &str, value: PropertyValue) {
+        let data = match value {
+            PropertyValue::Bool(value) => PropertyData::Bool(value),
+            PropertyValue::Number(value) => PropertyData::Number(value),
+            PropertyValue::String(value) => PropertyData::String(value.to_string()),
+        };
+
+        if let NodeKind::Element { properties, .. } = &mut element.data.borrow_mut().kind {
+            if let Some(property) = properties.iter_mut().find(|(n, _)| n == name) {
+                property.1 = data;
+            } else {
+                properties.push((name.to_string(), data));
+            }
+        } else {
+            panic!("actual node type does not match v-node type")
+        }
+    }
+
+    fn property_eq(&self, element: &MemoryNode, name: &str, value: PropertyValue) -> bool {
+        element.property_eq(name, value)
+    }
+
+    fn set_props(&self, element: &MemoryNode, value: Rc<dyn Any>) {
+        if let NodeKind::Element { props, .. } = &mut element.data.borrow_mut().kind {
+            *props = Some(value);
+        } else {
+            panic!("actual node type does not match v-node type")
+        }
+    }
+
+    fn add_style(&self, _key: &str, _css: &str) {
+        // A memory backend has no document to add styles to.
+    }
+
+    fn child_nodes(&self, parent: &MemoryNode) -> Vec<MemoryNode> {
+        parent.children()
+    }
+
+    fn append_child(&self, parent: &MemoryNode, child: &MemoryNode) {
+        child.detach();
+
+        let index = parent.data.borrow().children.len();
+
+        child.insert(parent, index);
+    }
+
+    fn insert_before(&self, parent: &MemoryNode, child: &MemoryNode, reference: &MemoryNode) {
+        child.detach();
+
+        let index = reference.index_in(parent);
+
+        child.insert(parent, index);
+    }
+
+    fn replace(&self, old: &MemoryNode, new: &MemoryNode) {
+        let parent = old
+            .parent()
+            .expect("cannot replace a node that has no parent");
+
+        new.detach();
+
+        let index = old.index_in(&parent);
+
+        old.detach();
+        new.insert(&parent, index);
+    }
+
+    fn remove(&self, node: &MemoryNode) {
+        node.detach();
+    }
+
+    fn tag_name(&self, node: &MemoryNode) -> Option<String> {
+        node.tag_name()
+    }
+
+    fn namespace(&self, node: &MemoryNode) -> Option<Namespace> {
+        node.namespace()
+    }
+
+    fn text(&self, node: &MemoryNode) -> Option<String> {
+        node.text()
+    }
+
+    fn attribute(&self, element: &MemoryNode, name: &Name) -> Option<String> {
+        element.attribute(name.as_ref())
+    }
+
+    fn attributes(&self, element: &MemoryNode) -> Vec<(String, String)> {
+        element.attributes()
+    }
+
+    fn dom_element(&self, _node: &MemoryNode) -> Option<DynamicElement> {
+        None
+    }
+}
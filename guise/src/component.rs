@@ -0,0 +1,181 @@
+use std::any::{Any, TypeId};
+
+use crate::vdom::{forward_on_rendered, Namespace};
+use crate::view_model::{ComponentWatcher, Updater, ViewModel};
+use crate::VDom;
+
+/// A lightweight component that renders into the virtual DOM of its parent.
+///
+/// Unlike a custom element registered with [register](crate::register), a component is not
+/// represented by an element of its own: the nodes it renders are spliced into the parent's
+/// virtual DOM, and its props are passed as typed Rust values rather than as string attributes.
+///
+/// A component keeps its state in a [ViewModel]. The state is initialized with [init] when the
+/// component is first rendered, and is carried over when the parent re-renders, as long as a
+/// component of the same type is rendered at the same position (or with the same key). Updating
+/// the state through the [Updater] passed to [render] re-renders only this component. Components
+/// that don't need state can use `()` as their [State](Component::State).
+///
+/// [init]: Component::init
+/// [render]: Component::render
+pub trait Component: 'static {
+    type Props: 'static;
+
+    type State: 'static;
+
+    fn init(&self, props: &Self::Props) -> Self::State;
+
+    /// Renders the component into the `vdom`.
+    ///
+    /// The nodes are placed in the namespace of the component's parent (e.g. a component inside
+    /// an `<svg>` element renders SVG elements). An `on_rendered` callback registered on the
+    /// `vdom` is called after the patch in which the component was rendered.
+    fn render(
+        &self,
+        props: &Self::Props,
+        state: &Self::State,
+        updater: &Updater<Self::State>,
+        vdom: &mut VDom,
+    );
+}
+
+/// A type-erased [Instance].
+pub(crate) trait AnyInstance {
+    /// The type ID of the component, rather than of the instance.
+    fn component_type_id(&self) -> TypeId;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// Carries over the state of the `old` instance, if it is an instance of the same component.
+    fn adopt(&mut self, old: &mut dyn AnyInstance);
+
+    /// Returns the rendered nodes of this instance, initializing its state and rendering it if it
+    /// has not been rendered yet.
+    fn rendered_mut(&mut self) -> &mut VDom;
+
+    /// Returns the rendered nodes of this instance, if it has been rendered.
+    fn rendered(&self) -> Option<&VDom>;
+
+    /// Renders this instance into a new virtual DOM, without storing the result.
+    fn render(&self) -> VDom;
+
+    /// Whether the state was updated since this instance was last rendered.
+    fn is_dirty(&self) -> bool;
+
+    /// Renders this instance again and returns the previously rendered nodes.
+    fn rerender(&mut self) -> Option<VDom>;
+}
+
+pub(crate) struct Instance<C>
+where
+    C: Component,
+{
+    component: C,
+    props: C::Props,
+    view_model: Option<ViewModel<C::State>>,
+    rendered: Option<VDom>,
+    /// The namespace context of the component's parent.
+    context: Namespace,
+}
+
+impl<C> Instance<C>
+where
+    C: Component,
+{
+    pub(crate) fn new(component: C, props: C::Props, context: Namespace) -> Self {
+        Instance {
+            component,
+            props,
+            view_model: None,
+            rendered: None,
+            context,
+        }
+    }
+
+    fn render_with(&self, view_model: &ViewModel<C::State>) -> VDom {
+        let updater = view_model.updater();
+
+        view_model.render_with(|state| {
+            let mut vdom = VDom::with_context(self.context);
+
+            self.component
+                .render(&self.props, state, &updater, &mut vdom);
+
+            vdom
+        })
+    }
+}
+
+impl<C> AnyInstance for Instance<C>
+where
+    C: Component,
+{
+    fn component_type_id(&self) -> TypeId {
+        TypeId::of::<C>()
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn adopt(&mut self, old: &mut dyn AnyInstance) {
+        if let Some(old) = old.as_any_mut().downcast_mut::<Instance<C>>() {
+            self.view_model = old.view_model.take();
+
+            // The old instance may have been rendered by an earlier render loop of the host
+            // element (e.g. before it was disconnected and connected again).
+            if let (Some(view_model), Some(watcher)) =
+                (&self.view_model, ComponentWatcher::current())
+            {
+                view_model.set_watcher(watcher);
+            }
+        }
+    }
+
+    fn rendered_mut(&mut self) -> &mut VDom {
+        if self.rendered.is_none() {
+            if self.view_model.is_none() {
+                // Updates to the state are reported to the host element's render loop.
+                self.view_model = Some(ViewModel::with_watcher(
+                    self.component.init(&self.props),
+                    ComponentWatcher::current(),
+                ));
+            }
+
+            let mut vdom = self.render_with(self.view_model.as_ref().unwrap());
+
+            forward_on_rendered(&mut vdom);
+
+            self.rendered = Some(vdom);
+        }
+
+        self.rendered.as_mut().unwrap()
+    }
+
+    fn rendered(&self) -> Option<&VDom> {
+        self.rendered.as_ref()
+    }
+
+    fn render(&self) -> VDom {
+        if let Some(view_model) = &self.view_model {
+            self.render_with(view_model)
+        } else {
+            self.render_with(&ViewModel::new(self.component.init(&self.props)))
+        }
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.view_model
+            .as_ref()
+            .map(|view_model| view_model.is_dirty())
+            .unwrap_or(false)
+    }
+
+    fn rerender(&mut self) -> Option<VDom> {
+        let mut vdom = self.render_with(self.view_model.as_ref().unwrap());
+
+        forward_on_rendered(&mut vdom);
+
+        self.rendered.replace(vdom)
+    }
+}
@@ -0,0 +1,397 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+use arwa::dom::DynamicElement;
+use futures::Stream;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+// Arwa does not support form-associated custom elements, so we bind `ElementInternals` directly.
+// Whether an element is form-associated is decided by `customElements.define` from the static
+// `formAssociated` property of the element's class, and the form callbacks are looked up on its
+// prototype at that time; `define_form_associated` intercepts the `define` call for the `name`
+// that `register` makes on the registry to add these to the class that Arwa defines. The
+// interception is removed when `register` returns, even if it throws (e.g. because the name is
+// already defined).
+#[wasm_bindgen(inline_js = r#"
+export function define_form_associated(registry, name, register) {
+    const own = Object.getOwnPropertyDescriptor(registry, "define");
+    const define = registry.define;
+
+    registry.define = function (defined_name, constructor, options) {
+        if (defined_name !== name) {
+            return define.call(registry, defined_name, constructor, options);
+        }
+
+        const prototype = constructor.prototype;
+
+        constructor.formAssociated = true;
+        prototype.formResetCallback = function () {
+            this.__guiseFormCallback && this.__guiseFormCallback(0, undefined, undefined);
+        };
+        prototype.formDisabledCallback = function (disabled) {
+            this.__guiseFormCallback && this.__guiseFormCallback(1, disabled, undefined);
+        };
+        prototype.formStateRestoreCallback = function (state, mode) {
+            this.__guiseFormCallback && this.__guiseFormCallback(2, state, mode);
+        };
+
+        return define.call(registry, defined_name, constructor, options);
+    };
+
+    try {
+        register();
+    } finally {
+        if (own) {
+            Object.defineProperty(registry, "define", own);
+        } else {
+            delete registry.define;
+        }
+    }
+}
+
+export function set_validity(internals, flags, message) {
+    internals.setValidity({
+        valueMissing: (flags & 1) !== 0,
+        typeMismatch: (flags & 2) !== 0,
+        patternMismatch: (flags & 4) !== 0,
+        tooLong: (flags & 8) !== 0,
+        tooShort: (flags & 16) !== 0,
+        rangeUnderflow: (flags & 32) !== 0,
+        rangeOverflow: (flags & 64) !== 0,
+        stepMismatch: (flags & 128) !== 0,
+        badInput: (flags & 256) !== 0,
+        customError: (flags & 512) !== 0,
+    }, message);
+}
+"#)]
+extern "C" {
+    pub(crate) fn define_form_associated(
+        registry: &JsValue,
+        name: &str,
+        register: &mut dyn FnMut(),
+    );
+
+    fn set_validity(internals: &JsValue, flags: u32, message: &str);
+}
+
+#[wasm_bindgen]
+extern "C" {
+    type JsElement;
+
+    #[wasm_bindgen(method, js_name = attachInternals)]
+    fn attach_internals(this: &JsElement) -> JsValue;
+
+    type JsElementInternals;
+
+    #[wasm_bindgen(method, js_name = setFormValue)]
+    fn set_form_value(this: &JsElementInternals, value: &JsValue, state: &JsValue);
+
+    #[wasm_bindgen(method, js_name = checkValidity)]
+    fn check_validity(this: &JsElementInternals) -> bool;
+
+    #[wasm_bindgen(method, js_name = reportValidity)]
+    fn report_validity(this: &JsElementInternals) -> bool;
+
+    #[wasm_bindgen(method, getter, js_name = validationMessage)]
+    fn validation_message(this: &JsElementInternals) -> String;
+
+    #[wasm_bindgen(method, getter)]
+    fn form(this: &JsElementInternals) -> JsValue;
+
+    #[wasm_bindgen(method, getter)]
+    fn states(this: &JsElementInternals) -> JsCustomStateSet;
+
+    type JsCustomStateSet;
+
+    #[wasm_bindgen(method)]
+    fn add(this: &JsCustomStateSet, state: &str);
+
+    #[wasm_bindgen(method)]
+    fn delete(this: &JsCustomStateSet, state: &str) -> bool;
+
+    #[wasm_bindgen(method)]
+    fn has(this: &JsCustomStateSet, state: &str) -> bool;
+
+    #[wasm_bindgen(js_namespace = Reflect, js_name = set)]
+    fn reflect_set(target: &JsValue, key: &str, value: &JsValue) -> bool;
+}
+
+/// The name of the JS property through which the prototype's form callbacks reach the element's
+/// [FormAssociation].
+const FORM_CALLBACK_KEY: &str = "__guiseFormCallback";
+
+/// The `ElementInternals` of a form-associated element (see
+/// [register_form_associated](crate::register_form_associated)), which let the element
+/// participate in form submission and validation.
+#[derive(Clone)]
+pub struct ElementInternals {
+    internals: JsValue,
+}
+
+impl ElementInternals {
+    fn js(&self) -> &JsElementInternals {
+        self.internals.unchecked_ref()
+    }
+
+    /// Wraps internals that were attached with `element.attachInternals()`.
+    pub(crate) fn new(internals: JsValue) -> Self {
+        ElementInternals { internals }
+    }
+
+    pub(crate) fn as_js(&self) -> &JsValue {
+        &self.internals
+    }
+
+    /// Sets the value that the element submits with its form.
+    pub fn set_form_value(&self, value: &str) {
+        self.js()
+            .set_form_value(&JsValue::from(value), &JsValue::UNDEFINED);
+    }
+
+    /// Sets the value that the element submits with its form, and the `state` that the browser
+    /// passes back to the element when it restores the element's state (see
+    /// [FormCallback::StateRestore]).
+    pub fn set_form_value_with_state(&self, value: &str, state: &str) {
+        self.js()
+            .set_form_value(&JsValue::from(value), &JsValue::from(state));
+    }
+
+    /// Clears the value of the element, so that the element submits nothing with its form.
+    pub fn clear_form_value(&self) {
+        self.js()
+            .set_form_value(&JsValue::NULL, &JsValue::UNDEFINED);
+    }
+
+    /// Marks the element as invalid for the given `flags`, with a validation `message` that is
+    /// reported to the user; marks the element as valid if none of the `flags` are set.
+    pub fn set_validity(&self, flags: ValidityFlags, message: &str) {
+        set_validity(&self.internals, flags.bits(), message);
+    }
+
+    /// Marks the element as valid.
+    pub fn set_valid(&self) {
+        self.set_validity(ValidityFlags::default(), "");
+    }
+
+    /// Returns `true` if the element is valid; fires an `invalid` event on the element otherwise.
+    pub fn check_validity(&self) -> bool {
+        self.js().check_validity()
+    }
+
+    /// Like [check_validity](ElementInternals::check_validity), but also reports the problem to
+    /// the user if the element is invalid.
+    pub fn report_validity(&self) -> bool {
+        self.js().report_validity()
+    }
+
+    pub fn validation_message(&self) -> String {
+        self.js().validation_message()
+    }
+
+    /// The form that the element is associated with, if any.
+    pub fn form(&self) -> Option<DynamicElement> {
+        let form = self.js().form();
+
+        if form.is_null() || form.is_undefined() {
+            None
+        } else {
+            Some(form.unchecked_into())
+        }
+    }
+
+    /// Adds the custom `state` to the element, which can be matched with the `:state()` CSS
+    /// pseudo-class.
+    pub fn add_state(&self, state: &str) {
+        self.js().states().add(state);
+    }
+
+    /// Removes the custom `state` from the element.
+    pub fn remove_state(&self, state: &str) {
+        self.js().states().delete(state);
+    }
+
+    /// Returns `true` if the element has the custom `state`.
+    pub fn has_state(&self, state: &str) -> bool {
+        self.js().states().has(state)
+    }
+}
+
+/// The validity flags for [ElementInternals::set_validity].
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct ValidityFlags {
+    pub value_missing: bool,
+    pub type_mismatch: bool,
+    pub pattern_mismatch: bool,
+    pub too_long: bool,
+    pub too_short: bool,
+    pub range_underflow: bool,
+    pub range_overflow: bool,
+    pub step_mismatch: bool,
+    pub bad_input: bool,
+    pub custom_error: bool,
+}
+
+impl ValidityFlags {
+    fn bits(&self) -> u32 {
+        [
+            self.value_missing,
+            self.type_mismatch,
+            self.pattern_mismatch,
+            self.too_long,
+            self.too_short,
+            self.range_underflow,
+            self.range_overflow,
+            self.step_mismatch,
+            self.bad_input,
+            self.custom_error,
+        ]
+        .iter()
+        .enumerate()
+        .fold(0, |bits, (i, flag)| bits | ((*flag as u32) << i))
+    }
+}
+
+/// A form callback invoked on a form-associated element.
+#[derive(Clone, PartialEq, Debug)]
+pub enum FormCallback {
+    /// The element's form was reset; the element should reset its value.
+    Reset,
+    /// The element was disabled (`true`) or enabled (`false`), e.g. because an ancestor
+    /// `<fieldset>` was disabled.
+    Disabled(bool),
+    /// The browser restored the element's state, e.g. after navigating back to the page.
+    StateRestore {
+        /// The state the element set with [ElementInternals::set_form_value_with_state] (or the
+        /// value it set with [ElementInternals::set_form_value]), if it was a string.
+        state: Option<String>,
+        mode: RestoreMode,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RestoreMode {
+    /// The state is restored after a navigation or a browser restart.
+    Restore,
+    /// The state is restored by the browser's autofill.
+    Autocomplete,
+}
+
+struct FormSlot {
+    queue: VecDeque<FormCallback>,
+    disconnected: bool,
+    waker: Option<Waker>,
+}
+
+/// The form association of an element registered with
+/// [register_form_associated](crate::register_form_associated).
+pub(crate) struct FormAssociation {
+    internals: ElementInternals,
+    slot: Rc<RefCell<FormSlot>>,
+    _callback: Closure<dyn FnMut(u32, JsValue, JsValue)>,
+}
+
+impl FormAssociation {
+    /// Attaches the `ElementInternals` of the `element`; must be called from the element's
+    /// constructor.
+    pub(crate) fn new(element: &JsValue) -> Self {
+        let internals = ElementInternals {
+            internals: element.unchecked_ref::<JsElement>().attach_internals(),
+        };
+        let slot = Rc::new(RefCell::new(FormSlot {
+            queue: VecDeque::new(),
+            disconnected: true,
+            waker: None,
+        }));
+
+        let callback = Closure::wrap(Box::new({
+            let slot = slot.clone();
+
+            move |kind: u32, arg_0: JsValue, arg_1: JsValue| {
+                let callback = match kind {
+                    0 => FormCallback::Reset,
+                    1 => FormCallback::Disabled(arg_0.is_truthy()),
+                    _ => FormCallback::StateRestore {
+                        state: arg_0.as_string(),
+                        mode: if arg_1.as_string().as_deref() == Some("autocomplete") {
+                            RestoreMode::Autocomplete
+                        } else {
+                            RestoreMode::Restore
+                        },
+                    },
+                };
+
+                let mut slot = slot.borrow_mut();
+
+                slot.queue.push_back(callback);
+
+                if let Some(waker) = slot.waker.take() {
+                    waker.wake();
+                }
+            }
+        }) as Box<dyn FnMut(u32, JsValue, JsValue)>);
+
+        reflect_set(element, FORM_CALLBACK_KEY, callback.as_ref());
+
+        FormAssociation {
+            internals,
+            slot,
+            _callback: callback,
+        }
+    }
+
+    pub(crate) fn internals(&self) -> &ElementInternals {
+        &self.internals
+    }
+
+    /// Marks the element as connected and returns a new [FormCallbacks] stream.
+    pub(crate) fn connect(&self) -> FormCallbacks {
+        self.slot.borrow_mut().disconnected = false;
+
+        FormCallbacks {
+            slot: self.slot.clone(),
+        }
+    }
+
+    /// Marks the element as disconnected, which ends the current [FormCallbacks] stream.
+    pub(crate) fn disconnect(&self) {
+        let mut slot = self.slot.borrow_mut();
+
+        slot.disconnected = true;
+        slot.queue.clear();
+
+        if let Some(waker) = slot.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// A stream of the form callbacks invoked on a form-associated element, in order.
+///
+/// Ends when the element is disconnected.
+pub struct FormCallbacks {
+    slot: Rc<RefCell<FormSlot>>,
+}
+
+impl Stream for FormCallbacks {
+    type Item = FormCallback;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut slot = self.slot.borrow_mut();
+
+        if slot.disconnected {
+            return Poll::Ready(None);
+        }
+
+        if let Some(callback) = slot.queue.pop_front() {
+            return Poll::Ready(Some(callback));
+        }
+
+        slot.waker = Some(cx.waker().clone());
+
+        Poll::Pending
+    }
+}
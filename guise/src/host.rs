@@ -0,0 +1,157 @@
+use std::cell::{Cell, RefCell};
+use std::ops::Deref;
+use std::rc::Rc;
+
+use arwa::dom::{Element, Name};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::form::ElementInternals;
+use crate::lifecycle::{Lifecycle, LifecycleDirector};
+use crate::slot::Slot;
+
+// Arwa does not expose `classList.toggle` with a `force` argument, so we bind it directly.
+#[wasm_bindgen]
+extern "C" {
+    type JsElement;
+
+    #[wasm_bindgen(method, getter, js_name = classList)]
+    fn class_list(this: &JsElement) -> JsClassList;
+
+    type JsClassList;
+
+    #[wasm_bindgen(method)]
+    fn toggle(this: &JsClassList, token: &str, force: bool) -> bool;
+}
+
+/// A handle to the host element of a component, passed to the component's `init` function.
+///
+/// Dereferences to the element. Can be cloned and moved into the component's tasks to reflect
+/// component state back to the host element's attributes and classes (e.g. `aria-expanded` or
+/// `open`).
+///
+/// Changes made through the handle to an observed attribute still update the attributes that the
+/// [AttributesChanged](crate::AttributesChanged) stream yields, but do not wake the stream, so a
+/// component that reflects its own state does not re-render in a loop.
+#[derive(Clone)]
+pub struct Host<E> {
+    element: E,
+    reflecting: Rc<Cell<bool>>,
+    internals: Option<ElementInternals>,
+    shadow_root: Option<JsValue>,
+    lifecycle: Rc<RefCell<LifecycleDirector>>,
+    connection: u64,
+}
+
+impl<E> Host<E>
+where
+    E: Element + AsRef<JsValue>,
+{
+    pub(crate) fn new(
+        element: E,
+        reflecting: Rc<Cell<bool>>,
+        internals: Option<ElementInternals>,
+        shadow_root: Option<JsValue>,
+        lifecycle: Rc<RefCell<LifecycleDirector>>,
+        connection: u64,
+    ) -> Self {
+        Host {
+            element,
+            reflecting,
+            internals,
+            shadow_root,
+            lifecycle,
+            connection,
+        }
+    }
+
+    /// Returns a stream of the lifecycle events of the host element during the connection for
+    /// which the component was initialized.
+    pub fn lifecycle(&self) -> Lifecycle {
+        self.lifecycle.borrow_mut().subscribe(self.connection)
+    }
+
+    /// Returns the slot with the given `name` in the host element's shadow root; pass an empty
+    /// `name` for the default slot.
+    ///
+    /// Only meaningful for components registered with a shadow root (e.g. with
+    /// [register_with_shadow_root](crate::register_with_shadow_root)).
+    pub fn slot(&self, name: &str) -> Slot {
+        Slot::new(
+            self.element.as_ref().clone(),
+            self.shadow_root.clone(),
+            name,
+        )
+    }
+
+    /// The `ElementInternals` of the host element, if the component was registered as
+    /// form-associated (see [register_form_associated](crate::register_form_associated)), or if
+    /// they were claimed to hydrate a closed declarative shadow root (see
+    /// [RegisterOptions::hydrate](crate::RegisterOptions::hydrate)).
+    pub fn internals(&self) -> Option<&ElementInternals> {
+        self.internals.as_ref()
+    }
+
+    /// Sets the attribute with the given `name` on the host element to `value`.
+    pub fn set_attribute(&self, name: &Name, value: &str) {
+        self.reflect(|| self.element.attributes().set(name, value));
+    }
+
+    /// Removes the attribute with the given `name` from the host element, if present.
+    pub fn remove_attribute(&self, name: &Name) {
+        self.reflect(|| self.element.attributes().remove(name));
+    }
+
+    /// Adds the boolean attribute with the given `name` to the host element if `present` is
+    /// `true`, or removes it otherwise.
+    pub fn toggle_attribute(&self, name: &Name, present: bool) {
+        if present {
+            self.set_attribute(name, "");
+        } else {
+            self.remove_attribute(name);
+        }
+    }
+
+    /// Adds the `class` to the host element's class list.
+    pub fn add_class(&self, class: &str) {
+        self.toggle_class(class, true);
+    }
+
+    /// Removes the `class` from the host element's class list.
+    pub fn remove_class(&self, class: &str) {
+        self.toggle_class(class, false);
+    }
+
+    /// Adds the `class` to the host element's class list if `present` is `true`, or removes it
+    /// otherwise.
+    pub fn toggle_class(&self, class: &str, present: bool) {
+        let js_ref: &JsValue = self.element.as_ref();
+
+        self.reflect(|| {
+            js_ref
+                .unchecked_ref::<JsElement>()
+                .class_list()
+                .toggle(class, present);
+        });
+    }
+
+    fn reflect<F>(&self, f: F)
+    where
+        F: FnOnce(),
+    {
+        // The browser invokes `attributeChangedCallback` synchronously, before `f` returns.
+        let was_reflecting = self.reflecting.replace(true);
+
+        f();
+
+        self.reflecting.set(was_reflecting);
+    }
+}
+
+impl<E> Deref for Host<E> {
+    type Target = E;
+
+    fn deref(&self) -> &E {
+        &self.element
+    }
+}
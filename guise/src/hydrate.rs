@@ -0,0 +1,519 @@
+use std::fmt;
+use std::iter::Peekable;
+
+use arwa::dom::name;
+
+use crate::backend::Backend;
+use crate::patch_dom::{attach, fresh_nodes, patch_properties, vdom_scope};
+use crate::scoped_style::ScopedStyle;
+use crate::util::warn;
+use crate::vdom::{Element, Node, VDom};
+
+/// Describes a difference between pre-rendered markup and the virtual DOM it was hydrated with.
+///
+/// Mismatches are repaired during hydration: the affected node is patched or replaced so that it
+/// matches the virtual DOM.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct HydrationMismatch {
+    path: Vec<usize>,
+    expected: String,
+    found: String,
+}
+
+impl HydrationMismatch {
+    /// The child indices that lead from the container to the node at which the mismatch occurred.
+    pub fn path(&self) -> &[usize] {
+        &self.path
+    }
+
+    /// A description of what the virtual DOM declares.
+    pub fn expected(&self) -> &str {
+        &self.expected
+    }
+
+    /// A description of what was found in the pre-rendered markup.
+    pub fn found(&self) -> &str {
+        &self.found
+    }
+}
+
+impl fmt::Display for HydrationMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "hydration mismatch at {:?}: expected {}, found {}",
+            self.path, self.expected, self.found
+        )
+    }
+}
+
+pub(crate) fn warn_hydration_mismatch(mismatch: &HydrationMismatch) {
+    warn(&mismatch.to_string());
+}
+
+/// Adopts the existing children of the `container` node as the rendering of the `new` virtual DOM.
+///
+/// Rather than creating new DOM nodes, sink tasks and element refs are attached to the existing
+/// nodes. Any differences between the existing nodes and the virtual DOM are reported to
+/// `on_mismatch` and then repaired. Nodes that are neither elements nor text nodes (e.g.
+/// comments) are removed. DOM properties are not part of pre-rendered markup; they are set
+/// without being reported as mismatches.
+pub fn hydrate_dom<B, F>(backend: &B, container: &B::Node, new: &mut VDom, mut on_mismatch: F)
+where
+    B: Backend,
+    F: FnMut(HydrationMismatch),
+{
+    let mut path = Vec::new();
+    let scope = vdom_scope(backend, new, None);
+
+    new.with_nodes_mut(|nodes| {
+        hydrate_children(
+            backend,
+            container,
+            nodes,
+            scope,
+            &mut path,
+            &mut on_mismatch,
+        );
+    });
+}
+
+fn hydrate_children<B>(
+    backend: &B,
+    parent: &B::Node,
+    new: &mut [Node],
+    scope: Option<ScopedStyle>,
+    path: &mut Vec<usize>,
+    on_mismatch: &mut dyn FnMut(HydrationMismatch),
+) where
+    B: Backend,
+{
+    let mut existing = Vec::new();
+
+    for node in backend.child_nodes(parent) {
+        if backend.tag_name(&node).is_some() || backend.text(&node).is_some() {
+            existing.push(node);
+        } else {
+            backend.remove(&node);
+        }
+    }
+
+    let mut existing = existing.into_iter().peekable();
+    let mut index = 0;
+
+    hydrate_nodes(
+        backend,
+        parent,
+        &mut existing,
+        new,
+        scope,
+        &mut index,
+        path,
+        on_mismatch,
+    );
+
+    for superfluous in existing {
+        path.push(index);
+
+        on_mismatch(HydrationMismatch {
+            path: path.clone(),
+            expected: "nothing".to_string(),
+            found: describe_node(backend, &superfluous),
+        });
+
+        path.pop();
+
+        backend.remove(&superfluous);
+
+        index += 1;
+    }
+}
+
+/// Hydrates the `new` nodes against the next nodes in `existing`.
+///
+/// The children of a fragment and the nodes rendered by a memo or component are hydrated as if
+/// they were children of the fragment's, memo's or component's parent; the `index` tracks the
+/// position in the parent's children.
+fn hydrate_nodes<B>(
+    backend: &B,
+    parent: &B::Node,
+    existing: &mut Peekable<impl Iterator<Item = B::Node>>,
+    new: &mut [Node],
+    scope: Option<ScopedStyle>,
+    index: &mut usize,
+    path: &mut Vec<usize>,
+    on_mismatch: &mut dyn FnMut(HydrationMismatch),
+) where
+    B: Backend,
+{
+    for node in new.iter_mut() {
+        if let Node::Fragment(fragment) = node {
+            hydrate_nodes(
+                backend,
+                parent,
+                existing,
+                fragment.children_mut(),
+                scope,
+                index,
+                path,
+                on_mismatch,
+            );
+
+            continue;
+        }
+
+        let rendered = match node {
+            Node::Memo(memo) => Some(memo.rendered_mut()),
+            Node::Component(component) => Some(component.instance_mut().rendered_mut()),
+            _ => None,
+        };
+
+        if let Some(vdom) = rendered {
+            let scope = vdom_scope(backend, vdom, scope);
+
+            vdom.with_nodes_mut(|nodes| {
+                hydrate_nodes(
+                    backend,
+                    parent,
+                    existing,
+                    nodes,
+                    scope,
+                    index,
+                    path,
+                    on_mismatch,
+                );
+            });
+
+            continue;
+        }
+
+        // An empty text node is not part of pre-rendered markup, so it cannot be matched against
+        // an existing node; it is created without being reported as a mismatch.
+        if let Node::Text("") = node {
+            let fresh = backend.create_text("");
+
+            if let Some(next) = existing.peek() {
+                backend.insert_before(parent, &fresh, next);
+            } else {
+                backend.append_child(parent, &fresh);
+            }
+
+            *index += 1;
+
+            continue;
+        }
+
+        path.push(*index);
+
+        if let Some(current) = existing.next() {
+            hydrate_node(backend, &current, node, scope, path, on_mismatch);
+        } else {
+            on_mismatch(HydrationMismatch {
+                path: path.clone(),
+                expected: describe_v_node(node),
+                found: "nothing".to_string(),
+            });
+
+            for fresh in fresh_nodes(backend, node, scope) {
+                backend.append_child(parent, &fresh);
+            }
+        }
+
+        path.pop();
+
+        *index += 1;
+    }
+}
+
+fn hydrate_node<B>(
+    backend: &B,
+    current: &B::Node,
+    new: &mut Node,
+    scope: Option<ScopedStyle>,
+    path: &mut Vec<usize>,
+    on_mismatch: &mut dyn FnMut(HydrationMismatch),
+) where
+    B: Backend,
+{
+    match new {
+        Node::Text(text) => {
+            if let Some(current_text) = backend.text(current) {
+                if current_text != *text {
+                    on_mismatch(HydrationMismatch {
+                        path: path.clone(),
+                        expected: format!("text {:?}", text),
+                        found: format!("text {:?}", current_text),
+                    });
+
+                    backend.set_text(current, text);
+                }
+
+                return;
+            }
+        }
+        Node::Element(element) => {
+            if is_same_element(backend, current, element) {
+                hydrate_attributes(backend, current, element, scope, path, on_mismatch);
+
+                // The scoping attribute is not part of the pre-rendered markup if the markup was
+                // not rendered with the scoped style; it is added without being reported as a
+                // mismatch.
+                if let Some(scope) = scope {
+                    let name = scope.attribute_name();
+
+                    if backend.attribute(current, &name).is_none() {
+                        backend.set_attribute(current, None, &name, "");
+                    }
+                }
+
+                hydrate_children(
+                    backend,
+                    current,
+                    element.children_mut(),
+                    scope,
+                    path,
+                    on_mismatch,
+                );
+                patch_properties(backend, current, element.properties());
+
+                if let Some(props) = element.props() {
+                    backend.set_props(current, props.value());
+                }
+
+                attach(backend, current, element);
+
+                return;
+            }
+        }
+        Node::Fragment(_) | Node::Memo(_) | Node::Component(_) => {
+            unreachable!("fragments, memos and components are hydrated by `hydrate_nodes`")
+        }
+    }
+
+    on_mismatch(HydrationMismatch {
+        path: path.clone(),
+        expected: describe_v_node(new),
+        found: describe_node(backend, current),
+    });
+
+    let fresh = fresh_nodes(backend, new, scope);
+
+    backend.replace(current, &fresh[0]);
+}
+
+fn is_same_element<B>(backend: &B, current: &B::Node, element: &Element) -> bool
+where
+    B: Backend,
+{
+    if backend.namespace(current) != Some(element.namespace()) {
+        return false;
+    }
+
+    let expected: &str = element.tag_name().as_ref();
+    let tag_name_matches = backend
+        .tag_name(current)
+        .map(|tag_name| unicase::eq_ascii(tag_name.as_str(), expected))
+        .unwrap_or(false);
+
+    if !tag_name_matches {
+        return false;
+    }
+
+    let is = backend.attribute(current, &name!("is"));
+
+    match (is.as_deref(), element.is()) {
+        (None, None) => true,
+        (Some(a), Some(b)) => a == AsRef::<str>::as_ref(b),
+        _ => false,
+    }
+}
+
+fn hydrate_attributes<B>(
+    backend: &B,
+    current: &B::Node,
+    element: &Element,
+    scope: Option<ScopedStyle>,
+    path: &[usize],
+    on_mismatch: &mut dyn FnMut(HydrationMismatch),
+) where
+    B: Backend,
+{
+    for attribute in element.attributes() {
+        let current_value = backend.attribute(current, attribute.name());
+
+        if current_value.as_deref() != Some(attribute.value()) {
+            let name: &str = attribute.name().as_ref();
+
+            on_mismatch(HydrationMismatch {
+                path: path.to_vec(),
+                expected: format!("attribute `{}={:?}`", name, attribute.value()),
+                found: current_value
+                    .map(|value| format!("attribute `{}={:?}`", name, value))
+                    .unwrap_or_else(|| format!("no `{}` attribute", name)),
+            });
+
+            backend.set_attribute(
+                current,
+                attribute.namespace(),
+                attribute.name(),
+                attribute.value(),
+            );
+        }
+    }
+
+    // Remove any attributes in the markup that are not in the virtual DOM. The `is` attribute is
+    // matched by `is_same_element` and the scoping attribute is handled by `hydrate_node`.
+    for (name, value) in backend.attributes(current) {
+        let is_declared = element
+            .attributes()
+            .iter()
+            .any(|attribute| unicase::eq_ascii(AsRef::<str>::as_ref(attribute.name()), &name));
+        let is_special = (element.is().is_some() && name == "is")
+            || scope
+                .map(|scope| scope.attribute() == name)
+                .unwrap_or(false);
+
+        if is_declared || is_special {
+            continue;
+        }
+
+        on_mismatch(HydrationMismatch {
+            path: path.to_vec(),
+            expected: format!("no `{}` attribute", name),
+            found: format!("attribute `{}={:?}`", name, value),
+        });
+
+        backend.remove_attribute(current, &name);
+    }
+}
+
+fn describe_v_node(node: &Node) -> String {
+    match node {
+        Node::Text(text) => format!("text {:?}", text),
+        Node::Element(element) => {
+            let tag_name: &str = element.tag_name().as_ref();
+
+            format!("element `<{}>`", tag_name)
+        }
+        Node::Fragment(_) => "fragment".to_string(),
+        Node::Memo(_) => "memo".to_string(),
+        Node::Component(_) => "component".to_string(),
+    }
+}
+
+fn describe_node<B>(backend: &B, node: &B::Node) -> String
+where
+    B: Backend,
+{
+    if let Some(tag_name) = backend.tag_name(node) {
+        format!("element `<{}>`", tag_name.to_ascii_lowercase())
+    } else if let Some(text) = backend.text(node) {
+        format!("text {:?}", text)
+    } else {
+        "unknown node".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::{MemoryBackend, MemoryNode};
+    use crate::patch_dom::patch_dom;
+    use crate::vdom::Namespace;
+
+    use super::*;
+
+    fn prerendered(f: impl FnOnce(&mut VDom)) -> MemoryNode {
+        let container = MemoryNode::container();
+        let mut vdom = VDom::new();
+
+        f(&mut vdom);
+        patch_dom(&MemoryBackend::new(), &container, VDom::new(), &mut vdom);
+
+        container
+    }
+
+    #[test]
+    fn reuses_matching_nodes() {
+        let container = prerendered(|vdom| {
+            vdom.child(name!("p"), |mut p| {
+                p.attr(name!("class"), "a");
+                p.text("text");
+            });
+        });
+        let element = container.children()[0].clone();
+        let mut vdom = VDom::new();
+        let mut mismatches = Vec::new();
+
+        vdom.child(name!("p"), |mut p| {
+            p.attr(name!("class"), "a");
+            p.text("text");
+        });
+
+        hydrate_dom(&MemoryBackend::new(), &container, &mut vdom, |mismatch| {
+            mismatches.push(mismatch)
+        });
+
+        assert!(mismatches.is_empty());
+        assert!(container.children()[0].ptr_eq(&element));
+    }
+
+    #[test]
+    fn replaces_elements_in_another_namespace() {
+        let backend = MemoryBackend::new();
+        let container = MemoryNode::container();
+        let stale = backend.create_element(Namespace::Html, &name!("svg"), None);
+
+        backend.append_child(&container, &stale);
+
+        let mut vdom = VDom::new();
+        let mut mismatches = Vec::new();
+
+        vdom.child(name!("svg"), |_| {});
+
+        hydrate_dom(&backend, &container, &mut vdom, |mismatch| {
+            mismatches.push(mismatch)
+        });
+
+        assert_eq!(mismatches.len(), 1);
+        assert!(!container.children()[0].ptr_eq(&stale));
+        assert_eq!(container.children()[0].namespace(), Some(Namespace::Svg));
+    }
+
+    #[test]
+    fn repairs_mismatches() {
+        let container = prerendered(|vdom| {
+            vdom.child(name!("p"), |mut p| {
+                p.attr(name!("class"), "stale");
+                p.attr(name!("title"), "extra");
+                p.text("old");
+            });
+            vdom.child(name!("span"), |_| {});
+            vdom.text("surplus");
+        });
+        let element = container.children()[0].clone();
+        let mut vdom = VDom::new();
+        let mut mismatches = Vec::new();
+
+        vdom.child(name!("p"), |mut p| {
+            p.attr(name!("class"), "fresh");
+            p.text("new");
+        });
+        vdom.child(name!("div"), |_| {});
+
+        hydrate_dom(&MemoryBackend::new(), &container, &mut vdom, |mismatch| {
+            mismatches.push(mismatch)
+        });
+
+        assert_eq!(
+            format!("{:?}", container),
+            "<p class=\"fresh\">new</p><div></div>"
+        );
+        assert!(container.children()[0].ptr_eq(&element));
+
+        let superfluous = mismatches.last().unwrap();
+
+        assert_eq!(superfluous.path(), &[2]);
+        assert_eq!(superfluous.expected(), "nothing");
+        assert_eq!(superfluous.found(), "text \"surplus\"");
+    }
+}
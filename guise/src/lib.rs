@@ -1,254 +1,1220 @@
 #![feature(allocator_api)]
 
 mod attributes;
+mod component;
 mod element_ref;
+mod form;
+mod host;
+mod hydrate;
 mod id_sink;
+mod lifecycle;
 mod listener;
 mod patch_dom;
+mod props;
 mod raw_sink;
+mod render_to_string;
+mod scoped_style;
 mod sink_spawner;
+mod slot;
+mod style_sheet;
+mod util;
 mod vdom;
 
+pub mod backend;
 pub mod flatten_abridged;
+pub mod svg;
 pub mod vdom_builder_ext;
 pub mod view_model;
 
-use std::cell::RefCell;
+use std::cell::{Cell, Ref, RefCell};
+use std::collections::VecDeque;
+use std::mem;
 use std::ops::Deref;
 use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll, Waker};
+use std::time::Duration;
 
-use arwa::dom::{Element, OwnedNode, ParentNode, ShadowHost, ShadowRootOptions};
+use arwa::dom::{
+    DynamicElement, Element, OwnedNode, ParentNode, ShadowHost, ShadowRoot, ShadowRootMode,
+    ShadowRootOptions,
+};
 use arwa::html::{
     AttributeChange, CustomElement, CustomElementDescriptor, CustomElementName,
     CustomElementRegistry, ExtendableElement,
 };
 use arwa::spawn_local;
+use futures::future::poll_fn;
 use futures::stream::{abortable, AbortHandle};
 use futures::{Stream, StreamExt};
-use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
 
-use crate::patch_dom::patch_dom;
+use crate::backend::{props_slot, ArwaBackend, ArwaNode, Backend};
+use crate::attributes::{initial_attributes, warn_attribute_error};
+use crate::form::{define_form_associated, FormAssociation};
+use crate::hydrate::warn_hydration_mismatch;
+use crate::lifecycle::LifecycleDirector;
+use crate::patch_dom::patch_components;
+use crate::props::{warn_props_type_error, PropsSlot};
+use crate::style_sheet::AdoptedRoot;
+use crate::vdom::{collect_on_rendered, OnRendered};
+use crate::view_model::ComponentWatcher;
 
-pub use crate::attributes::{Attribute, Attributes};
+pub use crate::attributes::{Attribute, AttributeError, AttributeParseError, Attributes};
+pub use crate::component::Component;
 pub use crate::element_ref::ElementRef;
+pub use crate::form::{ElementInternals, FormCallback, FormCallbacks, RestoreMode, ValidityFlags};
+pub use crate::host::Host;
+pub use crate::hydrate::{hydrate_dom, HydrationMismatch};
 pub use crate::id_sink::IdSink;
-pub use crate::listener::Listener;
-pub use crate::vdom::VDom;
+pub use crate::lifecycle::{Lifecycle, LifecycleEvent};
+pub use crate::listener::{Listener, ListenerOptions};
+pub use crate::patch_dom::patch_dom;
+pub use crate::props::{PropsChanged, PropsTypeError};
+pub use crate::render_to_string::{render_to_string, render_to_string_with_shadow_root};
+pub use crate::scoped_style::ScopedStyle;
+pub use crate::slot::{Slot, SlotAssignment, SlotChanges};
+pub use crate::style_sheet::{StyleSheet, StyleSheets};
+pub use crate::vdom::{FragmentBuilder, Namespace, PropertyValue, VDom};
 
-pub use guise_macro::Attributes;
+pub use guise_macro::{view, AttributeValue, Attributes};
 
 #[doc(hidden)]
 pub use arwa::dom::{name, Name};
+#[doc(hidden)]
+pub use arwa::html::custom_element_name;
 
-struct ComponentData<A> {
+struct ComponentData<A>
+where
+    A: Attributes,
+{
     attribute_change_director: Rc<RefCell<AttributeChangeDirector<A>>>,
-    last_vdom: RefCell<Option<VDom>>,
+    props_slot: Rc<RefCell<PropsSlot>>,
+    reflecting: Rc<Cell<bool>>,
+    form: Option<FormAssociation>,
+    /// The `ElementInternals` of the element: those of the [FormAssociation], or those claimed to
+    /// adopt a closed declarative shadow root (see [RegisterOptions::hydrate]).
+    internals: Option<ElementInternals>,
+    lifecycle: Rc<RefCell<LifecycleDirector>>,
+    /// Whether the element was disconnected, but is kept alive (see [DisconnectPolicy]).
+    parked: Cell<bool>,
+    disconnect_count: Cell<u64>,
+    /// The shadow root the component renders into, for components registered with a shadow
+    /// root; kept here because a closed shadow root is not exposed through the element.
+    shadow_root: Option<ShadowRoot>,
+    /// Keeps the shadow root subscribed to [RegisterOptions::style_sheets].
+    adopted_style_sheets: Option<Rc<AdoptedRoot>>,
+    last_vdom: Rc<RefCell<Option<VDom>>>,
     abort_handle: RefCell<Option<AbortHandle>>,
 }
 
-struct AttributeChangeDirector<A> {
+impl<A> ComponentData<A>
+where
+    A: Attributes,
+{
+    fn new(element: &JsValue, form_associated: bool) -> Self {
+        let form = form_associated.then(|| FormAssociation::new(element));
+        let internals = form.as_ref().map(|form| form.internals().clone());
+
+        ComponentData {
+            attribute_change_director: Rc::new(RefCell::new(AttributeChangeDirector::new())),
+            props_slot: props_slot(element, true),
+            reflecting: Rc::new(Cell::new(false)),
+            form,
+            internals,
+            lifecycle: Rc::new(RefCell::new(LifecycleDirector::new())),
+            parked: Cell::new(false),
+            disconnect_count: Cell::new(0),
+            shadow_root: None,
+            adopted_style_sheets: None,
+            last_vdom: Rc::new(RefCell::new(None)),
+            abort_handle: RefCell::new(None),
+        }
+    }
+}
+
+struct AttributeChangeDirector<A>
+where
+    A: Attributes,
+{
     attributes: A,
+    diff: A::Diff,
     waker: Option<Waker>,
     disconnected: bool,
+    /// Parse errors that have not yet been yielded by the [AttributeErrors] stream; errors that
+    /// occur before the component is initialized are held until we know whether the component
+    /// subscribes to them.
+    errors: VecDeque<AttributeError>,
+    errors_subscribed: bool,
+    errors_waker: Option<Waker>,
+}
+
+impl<A> AttributeChangeDirector<A>
+where
+    A: Attributes,
+{
+    fn new() -> Self {
+        AttributeChangeDirector {
+            attributes: initial_attributes(),
+            diff: Default::default(),
+            waker: None,
+            disconnected: true,
+            errors: VecDeque::new(),
+            errors_subscribed: false,
+            errors_waker: None,
+        }
+    }
+
+    /// Records a change of the attribute with the given `name`; parse errors are passed to
+    /// `on_error`, unless they are held for the [AttributeErrors] stream.
+    fn change(&mut self, name: &Name, value: Option<String>, on_error: fn(&AttributeError)) {
+        A::record_change(&mut self.diff, name);
+
+        if let Err(error) = self.attributes.update(name, value) {
+            if self.disconnected || self.errors_subscribed {
+                self.errors.push_back(error);
+
+                if let Some(waker) = self.errors_waker.take() {
+                    waker.wake();
+                }
+            } else {
+                on_error(&error);
+            }
+        }
+    }
+
+    /// Wakes the [AttributesChanged] stream of the current connection.
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// Ends the [AttributesChanged] and [AttributeErrors] streams of the current connection, and
+    /// resets the attributes for the next connection.
+    fn disconnect(&mut self) {
+        self.wake();
+
+        self.attributes = initial_attributes();
+        self.diff = Default::default();
+        self.disconnected = true;
+        self.errors.clear();
+        self.errors_subscribed = false;
+
+        if let Some(waker) = self.errors_waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// Returns the diff of the attributes that changed since the previous change.
+    fn poll_change(&mut self, cx: &mut Context<'_>) -> Poll<Option<A::Diff>> {
+        if self.disconnected {
+            Poll::Ready(None)
+        } else if self.waker.is_some() {
+            Poll::Pending
+        } else {
+            self.waker = Some(cx.waker().clone());
+
+            Poll::Ready(Some(mem::take(&mut self.diff)))
+        }
+    }
 }
 
-pub struct AttributesChanged<A> {
+pub struct AttributesChanged<A>
+where
+    A: Attributes,
+{
     director: Rc<RefCell<AttributeChangeDirector<A>>>,
 }
 
-impl<A> Stream for AttributesChanged<A>
+impl<A> AttributesChanged<A>
 where
     A: Attributes,
 {
-    type Item = A;
+    /// Returns a stream that yields which attributes changed, rather than all attributes.
+    ///
+    /// The diff of the first item records the attributes that were set before the element was
+    /// connected. The current attributes can be read with [AttributeDiffs::attributes] without
+    /// cloning them.
+    pub fn diffs(self) -> AttributeDiffs<A> {
+        AttributeDiffs {
+            director: self.director,
+        }
+    }
+
+    /// Returns a stream of the errors for observed attribute values that fail to parse (see
+    /// [Attributes::update]), including errors for the values the element had when the component
+    /// was initialized.
+    ///
+    /// Once a component subscribes to its errors, they are no longer passed to
+    /// [RegisterOptions::on_attribute_error]. The stream ends when the element is disconnected.
+    pub fn errors(&self) -> AttributeErrors<A> {
+        self.director.borrow_mut().errors_subscribed = true;
+
+        AttributeErrors {
+            director: self.director.clone(),
+        }
+    }
+}
+
+/// A stream of attribute parse errors (see [AttributesChanged::errors]).
+pub struct AttributeErrors<A>
+where
+    A: Attributes,
+{
+    director: Rc<RefCell<AttributeChangeDirector<A>>>,
+}
+
+impl<A> Stream for AttributeErrors<A>
+where
+    A: Attributes,
+{
+    type Item = AttributeError;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let this = self.get_mut();
-        let mut director = this.director.borrow_mut();
+        let mut director = self.director.borrow_mut();
 
-        if director.disconnected {
+        if let Some(error) = director.errors.pop_front() {
+            Poll::Ready(Some(error))
+        } else if director.disconnected {
             Poll::Ready(None)
-        } else if director.waker.is_some() {
-            Poll::Pending
         } else {
-            director.waker = Some(cx.waker().clone());
+            director.errors_waker = Some(cx.waker().clone());
 
-            Poll::Ready(Some(director.attributes.clone()))
+            Poll::Pending
         }
     }
 }
 
-pub fn register<E, A, S, F>(registry: &CustomElementRegistry, name: &CustomElementName, mut init: F)
+impl<A> Stream for AttributesChanged<A>
 where
-    E: Element + ParentNode + OwnedNode + ExtendableElement + Clone + AsRef<JsValue> + 'static,
-    A: Attributes + 'static,
-    S: Stream<Item = VDom> + Unpin + 'static,
-    F: FnMut(&E, AttributesChanged<A>) -> S + 'static,
+    A: Attributes,
 {
-    let descriptor = CustomElementDescriptor::new(move |_: &E| ComponentData {
-        attribute_change_director: Rc::new(RefCell::new(AttributeChangeDirector {
-            attributes: A::default(),
-            waker: None,
-            disconnected: true,
-        })),
-        last_vdom: RefCell::new(None),
-        abort_handle: RefCell::new(None),
-    })
-    .connected_callback(move |element| {
-        let element = element.clone();
-        let director = element.data().attribute_change_director.clone();
+    type Item = A;
 
-        {
-            let mut director = director.borrow_mut();
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut director = self.director.borrow_mut();
 
-            director.disconnected = false;
-        }
+        director
+            .poll_change(cx)
+            .map(|change| change.map(|_| director.attributes.clone()))
+    }
+}
 
-        let attributes_changed = AttributesChanged { director };
-        let (mut vdoms, abort_handle) = abortable(init(element.deref(), attributes_changed));
+/// A stream of diffs of the attributes of an element that changed (see
+/// [AttributesChanged::diffs]).
+pub struct AttributeDiffs<A>
+where
+    A: Attributes,
+{
+    director: Rc<RefCell<AttributeChangeDirector<A>>>,
+}
+
+impl<A> AttributeDiffs<A>
+where
+    A: Attributes,
+{
+    /// Returns the current attributes of the element.
+    ///
+    /// Don't hold on to the returned reference across an `await` or while changing the element's
+    /// attributes: the attributes are updated when the element's attributes change, which panics
+    /// while they are borrowed.
+    pub fn attributes(&self) -> Ref<'_, A> {
+        Ref::map(self.director.borrow(), |director| &director.attributes)
+    }
+}
 
-        element.data().abort_handle.replace(Some(abort_handle));
+impl<A> Stream for AttributeDiffs<A>
+where
+    A: Attributes,
+{
+    type Item = A::Diff;
 
-        let document = element
-            .owner_document()
-            .try_into()
-            .expect("Guise only supports HTML documents");
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.director.borrow_mut().poll_change(cx)
+    }
+}
 
-        spawn_local(async move {
-            while let Some(mut new) = vdoms.next().await {
-                let mut last_vdom = element.data().last_vdom.borrow_mut();
+/// Options that control how a component registered with [register_with_options] or
+/// [register_with_shadow_root_and_options] renders.
+#[derive(Clone)]
+pub struct RegisterOptions {
+    /// Whether the first render of an element should hydrate the element's existing content.
+    ///
+    /// When enabled, the first [VDom] rendered after an element is connected adopts the existing
+    /// child nodes (e.g. pre-rendered with [render_to_string]) instead of appending fresh nodes:
+    /// sink tasks and [ElementRef]s are attached to the existing nodes and any differences are
+    /// reported to [on_hydration_mismatch](RegisterOptions::on_hydration_mismatch) and repaired.
+    /// For components registered with a shadow root, an existing declarative shadow root (e.g.
+    /// pre-rendered with [render_to_string_with_shadow_root]) is adopted and hydrated, even if it
+    /// is closed. Defaults to `false`.
+    ///
+    /// A closed shadow root is only exposed through the element's `ElementInternals`, so when the
+    /// component is registered with a closed shadow root, hydrating claims the internals of
+    /// elements that are not form-associated: `attachInternals` can then not be called on the
+    /// element again, but the internals are available through [Host::internals].
+    ///
+    /// The declarative shadow root must already be attached when the element is constructed. If
+    /// the component is registered before the parser reaches the element (e.g. by a blocking
+    /// script in the document's `<head>`), the element is constructed as soon as its start tag is
+    /// parsed, before its `<template shadowrootmode>` child: it then gets a fresh shadow root,
+    /// and the parser leaves the template in the element's light DOM. Register such components
+    /// after the document is parsed (e.g. from a module or `defer` script).
+    pub hydrate: bool,
 
-                let old = last_vdom.take().unwrap_or(VDom::new());
+    /// Invoked for every mismatch found while hydrating. Defaults to logging a warning to the
+    /// console.
+    pub on_hydration_mismatch: fn(&HydrationMismatch),
 
-                patch_dom(&document, element.deref(), old, &mut new);
+    /// Invoked for every observed attribute value that fails to parse (see
+    /// [Attributes::update]), unless the component receives its errors through
+    /// [AttributesChanged::errors]. Defaults to logging a warning to the console.
+    pub on_attribute_error: fn(&AttributeError),
 
-                if let Some(on_rendered) = new.on_rendered.take() {
-                    let js_ref: &JsValue = element.as_ref();
+    /// Invoked when a parent passes props that are not of the type the component expects (see
+    /// [PropsChanged]). Defaults to logging a warning to the console.
+    pub on_props_type_error: fn(&PropsTypeError),
 
-                    on_rendered(js_ref.unchecked_ref());
-                }
+    /// What happens to a component when its element is disconnected. Defaults to
+    /// [DisconnectPolicy::Reinitialize].
+    pub disconnect_policy: DisconnectPolicy,
 
-                // Note: this drops the previous vdom (if any), which should abort all old sink
-                // tasks.
-                *last_vdom = Some(new);
-            }
-        });
-    })
-    .disconnected_callback(disconnected_callback::<A, E>)
-    .attribute_changed_callback(A::OBSERVED, attribute_changed_callback::<A, E>);
+    /// Style sheets that the shadow root of every instance adopts (through `adoptedStyleSheets`);
+    /// only used by components registered with a shadow root. Defaults to `None`.
+    pub style_sheets: Option<StyleSheets>,
+}
+
+impl Default for RegisterOptions {
+    fn default() -> Self {
+        RegisterOptions {
+            hydrate: false,
+            on_hydration_mismatch: warn_hydration_mismatch,
+            on_attribute_error: warn_attribute_error,
+            on_props_type_error: warn_props_type_error,
+            disconnect_policy: DisconnectPolicy::Reinitialize,
+            style_sheets: None,
+        }
+    }
+}
+
+/// Determines what happens to a component when its element is disconnected (see
+/// [RegisterOptions::disconnect_policy]).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DisconnectPolicy {
+    /// The component is torn down immediately: its vdom stream is aborted and its attributes are
+    /// reset. When the element is connected again, `init` is called again.
+    Reinitialize,
+    /// The component is kept alive for the given grace period. If the element is connected again
+    /// within the grace period (e.g. because it was moved within the DOM), the component resumes
+    /// with its existing vdom stream and state; otherwise it is torn down as with
+    /// [DisconnectPolicy::Reinitialize].
+    ///
+    /// A grace period of zero keeps the component alive until the end of the current task, which
+    /// suffices for an element that is removed and reinserted by the same script. A component
+    /// that is kept alive does not observe the disconnect in its [Lifecycle] stream, and its
+    /// stream receives no event when the component resumes: no [LifecycleEvent::Disconnected]
+    /// and no new [LifecycleEvent::Connected] (a move to a new document is still reported as
+    /// [LifecycleEvent::Adopted]).
+    KeepAlive(Duration),
+}
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = setTimeout)]
+    fn set_timeout(handler: &JsValue, timeout: i32) -> i32;
+}
+
+pub fn register<E, A, S, F>(registry: &CustomElementRegistry, name: &CustomElementName, init: F)
+where
+    E: Element + ParentNode + OwnedNode + ExtendableElement + Clone + AsRef<JsValue> + 'static,
+    A: Attributes + 'static,
+    S: Stream<Item = VDom> + Unpin + 'static,
+    F: FnMut(&Host<E>, AttributesChanged<A>) -> S + 'static,
+{
+    register_with_options(registry, name, RegisterOptions::default(), init);
+}
+
+pub fn register_with_options<E, A, S, F>(
+    registry: &CustomElementRegistry,
+    name: &CustomElementName,
+    options: RegisterOptions,
+    mut init: F,
+) where
+    E: Element + ParentNode + OwnedNode + ExtendableElement + Clone + AsRef<JsValue> + 'static,
+    A: Attributes + 'static,
+    S: Stream<Item = VDom> + Unpin + 'static,
+    F: FnMut(&Host<E>, AttributesChanged<A>) -> S + 'static,
+{
+    register_with_props(
+        registry,
+        name,
+        options,
+        move |element, attributes_changed, _: PropsChanged<()>| init(element, attributes_changed),
+    );
+}
 
-    registry.register(name, descriptor);
+/// Registers a component that receives typed props from its parent.
+///
+/// In addition to the [AttributesChanged] stream, the `init` function receives a [PropsChanged]
+/// stream of the props that a parent passes to the element with `ElementBuilder::props`.
+pub fn register_with_props<E, A, P, S, F>(
+    registry: &CustomElementRegistry,
+    name: &CustomElementName,
+    options: RegisterOptions,
+    mut init: F,
+) where
+    E: Element + ParentNode + OwnedNode + ExtendableElement + Clone + AsRef<JsValue> + 'static,
+    A: Attributes + 'static,
+    P: 'static,
+    S: Stream<Item = VDom> + Unpin + 'static,
+    F: FnMut(&Host<E>, AttributesChanged<A>, PropsChanged<P>) -> S + 'static,
+{
+    register_impl(
+        registry,
+        name,
+        Container::Host,
+        false,
+        options,
+        move |host, attributes_changed, props_changed, _| {
+            init(host, attributes_changed, props_changed)
+        },
+    );
 }
 
 pub fn register_with_shadow_root<E, A, S, F>(
     registry: &CustomElementRegistry,
     shadow_root_options: ShadowRootOptions,
     name: &CustomElementName,
+    init: F,
+) where
+    E: ShadowHost + Element + ParentNode + OwnedNode + ExtendableElement + Clone + AsRef<JsValue> + 'static,
+    A: Attributes + 'static,
+    S: Stream<Item = VDom> + Unpin + 'static,
+    F: FnMut(&Host<E>, AttributesChanged<A>) -> S + 'static,
+{
+    register_with_shadow_root_and_options(
+        registry,
+        shadow_root_options,
+        name,
+        RegisterOptions::default(),
+        init,
+    );
+}
+
+pub fn register_with_shadow_root_and_options<E, A, S, F>(
+    registry: &CustomElementRegistry,
+    shadow_root_options: ShadowRootOptions,
+    name: &CustomElementName,
+    options: RegisterOptions,
     mut init: F,
 ) where
     E: ShadowHost + Element + ParentNode + OwnedNode + ExtendableElement + Clone + AsRef<JsValue> + 'static,
     A: Attributes + 'static,
     S: Stream<Item = VDom> + Unpin + 'static,
-    F: FnMut(&E, AttributesChanged<A>) -> S + 'static,
+    F: FnMut(&Host<E>, AttributesChanged<A>) -> S + 'static,
 {
-    let descriptor = CustomElementDescriptor::new(move |element: &E| {
-        element.attach_shadow(shadow_root_options);
+    register_with_shadow_root_and_props(
+        registry,
+        shadow_root_options,
+        name,
+        options,
+        move |element, attributes_changed, _: PropsChanged<()>| init(element, attributes_changed),
+    );
+}
 
-        ComponentData {
-            attribute_change_director: Rc::new(RefCell::new(AttributeChangeDirector {
-                attributes: A::default(),
-                waker: None,
-                disconnected: true,
-            })),
-            last_vdom: RefCell::new(None),
-            abort_handle: RefCell::new(None),
+/// Registers a component that renders into a shadow root and receives typed props from its
+/// parent.
+///
+/// See [register_with_props] for details.
+pub fn register_with_shadow_root_and_props<E, A, P, S, F>(
+    registry: &CustomElementRegistry,
+    shadow_root_options: ShadowRootOptions,
+    name: &CustomElementName,
+    options: RegisterOptions,
+    mut init: F,
+) where
+    E: ShadowHost + Element + ParentNode + OwnedNode + ExtendableElement + Clone + AsRef<JsValue> + 'static,
+    A: Attributes + 'static,
+    P: 'static,
+    S: Stream<Item = VDom> + Unpin + 'static,
+    F: FnMut(&Host<E>, AttributesChanged<A>, PropsChanged<P>) -> S + 'static,
+{
+    register_impl(
+        registry,
+        name,
+        Container::ShadowRoot {
+            options: shadow_root_options,
+            attach: |element: &E, options| element.attach_shadow(options),
+        },
+        false,
+        options,
+        move |host, attributes_changed, props_changed, _| {
+            init(host, attributes_changed, props_changed)
+        },
+    );
+}
+
+/// Registers a form-associated component, which can participate in form submission, validation
+/// and `form.reset()` like a built-in form control.
+///
+/// The component's [Host] exposes the element's [ElementInternals], and the `init` function
+/// receives a [FormCallbacks] stream in addition to the streams it receives with
+/// [register_with_props]. Form-associated components must be autonomous custom elements (not
+/// customized built-in elements).
+pub fn register_form_associated<E, A, P, S, F>(
+    registry: &CustomElementRegistry,
+    name: &CustomElementName,
+    options: RegisterOptions,
+    mut init: F,
+) where
+    E: Element + ParentNode + OwnedNode + ExtendableElement + Clone + AsRef<JsValue> + 'static,
+    A: Attributes + 'static,
+    P: 'static,
+    S: Stream<Item = VDom> + Unpin + 'static,
+    F: FnMut(&Host<E>, AttributesChanged<A>, PropsChanged<P>, FormCallbacks) -> S + 'static,
+{
+    register_impl(
+        registry,
+        name,
+        Container::Host,
+        true,
+        options,
+        move |host, attributes_changed, props_changed, form_callbacks| {
+            init(host, attributes_changed, props_changed, form_callbacks.unwrap())
+        },
+    );
+}
+
+/// Registers a form-associated component that renders into a shadow root.
+///
+/// See [register_form_associated] for details.
+pub fn register_form_associated_with_shadow_root<E, A, P, S, F>(
+    registry: &CustomElementRegistry,
+    shadow_root_options: ShadowRootOptions,
+    name: &CustomElementName,
+    options: RegisterOptions,
+    mut init: F,
+) where
+    E: ShadowHost + Element + ParentNode + OwnedNode + ExtendableElement + Clone + AsRef<JsValue> + 'static,
+    A: Attributes + 'static,
+    P: 'static,
+    S: Stream<Item = VDom> + Unpin + 'static,
+    F: FnMut(&Host<E>, AttributesChanged<A>, PropsChanged<P>, FormCallbacks) -> S + 'static,
+{
+    register_impl(
+        registry,
+        name,
+        Container::ShadowRoot {
+            options: shadow_root_options,
+            attach: |element: &E, options| element.attach_shadow(options),
+        },
+        true,
+        options,
+        move |host, attributes_changed, props_changed, form_callbacks| {
+            init(host, attributes_changed, props_changed, form_callbacks.unwrap())
+        },
+    );
+}
+
+/// Where a component renders its vdoms.
+enum Container<E> {
+    /// Into the host element itself.
+    Host,
+    /// Into a shadow root that is attached to the host element with the given `options`.
+    ShadowRoot {
+        options: ShadowRootOptions,
+        attach: fn(&E, ShadowRootOptions) -> ShadowRoot,
+    },
+}
+
+/// Registers a component that renders into the given `container`; shared by all `register`
+/// functions.
+///
+/// The `init` function receives a [FormCallbacks] stream if the component is `form_associated`.
+fn register_impl<E, A, P, S, F>(
+    registry: &CustomElementRegistry,
+    name: &CustomElementName,
+    container: Container<E>,
+    form_associated: bool,
+    options: RegisterOptions,
+    mut init: F,
+) where
+    E: Element + ParentNode + OwnedNode + ExtendableElement + Clone + AsRef<JsValue> + 'static,
+    A: Attributes + 'static,
+    P: 'static,
+    S: Stream<Item = VDom> + Unpin + 'static,
+    F: FnMut(&Host<E>, AttributesChanged<A>, PropsChanged<P>, Option<FormCallbacks>) -> S
+        + 'static,
+{
+    let options = Rc::new(options);
+    let descriptor = CustomElementDescriptor::new({
+        let options = options.clone();
+
+        move |element: &E| {
+            let js_ref: &JsValue = element.as_ref();
+            let mut data = ComponentData::new(js_ref, form_associated);
+
+            if let Container::ShadowRoot {
+                options: shadow_root_options,
+                attach,
+            } = &container
+            {
+                attach_shadow_root(element, &mut data, *shadow_root_options, *attach, &options);
+            }
+
+            data
         }
     })
-    .connected_callback(move |element| {
-        let element = element.clone();
-        let director = element.data().attribute_change_director.clone();
+    .connected_callback({
+        let options = options.clone();
 
-        {
-            let mut director = director.borrow_mut();
+        move |element| {
+            let container = if let Some(shadow_root) = element.data().shadow_root.clone() {
+                ArwaNode::from(shadow_root)
+            } else {
+                let js_ref: &JsValue = element.as_ref();
 
-            director.disconnected = false;
+                ArwaNode::from(js_ref.unchecked_ref::<DynamicElement>().clone())
+            };
+
+            // Connect the form callbacks only when `init` is called, which does not happen when a
+            // parked component resumes: it keeps its existing form callbacks stream.
+            connected_callback(
+                element,
+                container,
+                &options,
+                &mut |host: &Host<E>, attributes, props| {
+                    let form_callbacks = element.data().form.as_ref().map(|form| form.connect());
+
+                    init(host, attributes, props, form_callbacks)
+                },
+            );
         }
+    })
+    .disconnected_callback({
+        let options = options.clone();
 
-        let attributes_changed = AttributesChanged { director };
-        let (mut vdoms, abort_handle) = abortable(init(element.deref(), attributes_changed));
+        move |element| disconnected_callback(element, &options)
+    })
+    .adopted_callback(adopted_callback::<A, E>)
+    .attribute_changed_callback(A::OBSERVED, move |element, change| {
+        attribute_changed_callback(element, change, &options)
+    });
 
-        element.data().abort_handle.replace(Some(abort_handle));
+    if form_associated {
+        let js_ref: &JsValue = registry.as_ref();
+        let name_str: &str = name.as_ref();
+        let mut descriptor = Some(descriptor);
 
-        let document = element
-            .owner_document()
-            .try_into()
-            .expect("Guise only supports HTML documents");
-        let shadow_root = element.shadow_root().unwrap();
+        define_form_associated(js_ref, name_str, &mut || {
+            registry.register(name, descriptor.take().unwrap());
+        });
+    } else {
+        registry.register(name, descriptor);
+    }
+}
 
-        spawn_local(async move {
-            while let Some(mut new) = vdoms.next().await {
-                let mut last_vdom = element.data().last_vdom.borrow_mut();
+// A closed shadow root is not exposed through `element.shadowRoot`, but a declarative shadow root
+// is exposed to the element itself through its `ElementInternals`.
+#[wasm_bindgen(inline_js = r#"
+export function open_shadow_root(element) {
+    return element.shadowRoot;
+}
+
+export function claim_internals(element) {
+    try {
+        return element.attachInternals();
+    } catch (e) {
+        // Customized built-in elements do not support `attachInternals`.
+        return null;
+    }
+}
 
-                let old = last_vdom.take().unwrap_or(VDom::new());
+export function internals_shadow_root(internals) {
+    return internals.shadowRoot;
+}
+"#)]
+extern "C" {
+    fn open_shadow_root(element: &JsValue) -> JsValue;
 
-                patch_dom(&document, &shadow_root, old, &mut new);
+    fn claim_internals(element: &JsValue) -> JsValue;
 
-                if let Some(on_rendered) = new.on_rendered.take() {
-                    let js_ref: &JsValue = element.as_ref();
+    fn internals_shadow_root(internals: &JsValue) -> JsValue;
+}
 
-                    on_rendered(js_ref.unchecked_ref());
-                }
+/// Attaches the shadow root that the component renders into, and lets it adopt the
+/// [RegisterOptions::style_sheets].
+///
+/// When hydrating, adopts the declarative shadow root if the parser already attached one (see
+/// [render_to_string_with_shadow_root]); attaching a new shadow root would discard its
+/// pre-rendered content. A closed shadow root can only be found through the element's
+/// `ElementInternals`: a form-associated element uses its existing internals, other elements claim
+/// theirs (see [RegisterOptions::hydrate]).
+fn attach_shadow_root<A, E>(
+    element: &E,
+    data: &mut ComponentData<A>,
+    shadow_root_options: ShadowRootOptions,
+    attach: fn(&E, ShadowRootOptions) -> ShadowRoot,
+    options: &RegisterOptions,
+) where
+    E: AsRef<JsValue>,
+    A: Attributes,
+{
+    let existing = if !options.hydrate {
+        JsValue::NULL
+    } else if matches!(shadow_root_options.mode, ShadowRootMode::Closed) {
+        if data.internals.is_none() {
+            let claimed = claim_internals(element.as_ref());
 
-                // Note: this drops the previous vdom (if any), which should abort all old sink
-                // tasks.
-                *last_vdom = Some(new);
+            if !claimed.is_null() {
+                data.internals = Some(ElementInternals::new(claimed));
             }
+        }
+
+        data.internals
+            .as_ref()
+            .map(|internals| internals_shadow_root(internals.as_js()))
+            .unwrap_or(JsValue::NULL)
+    } else {
+        open_shadow_root(element.as_ref())
+    };
+    let existing = (!existing.is_null() && !existing.is_undefined())
+        .then(|| existing.unchecked_into::<ShadowRoot>());
+
+    let shadow_root = existing.unwrap_or_else(|| attach(element, shadow_root_options));
+
+    if let Some(style_sheets) = &options.style_sheets {
+        let js_ref: &JsValue = shadow_root.as_ref();
+
+        data.adopted_style_sheets = Some(style_sheets.adopt(js_ref));
+    }
+
+    data.shadow_root = Some(shadow_root);
+}
+
+fn connected_callback<A, E, P, S, F>(
+    element: &CustomElement<ComponentData<A>, E>,
+    container: ArwaNode,
+    options: &RegisterOptions,
+    init: &mut F,
+) where
+    E: Element + ParentNode + OwnedNode + ExtendableElement + Clone + AsRef<JsValue> + 'static,
+    A: Attributes + 'static,
+    P: 'static,
+    S: Stream<Item = VDom> + Unpin + 'static,
+    F: FnMut(&Host<E>, AttributesChanged<A>, PropsChanged<P>) -> S,
+{
+    // Resume a component that was kept alive after it was disconnected.
+    if element.data().parked.replace(false) {
+        return;
+    }
+
+    let director = element.data().attribute_change_director.clone();
+
+    director.borrow_mut().disconnected = false;
+
+    let props_slot = element.data().props_slot.clone();
+
+    props_slot.borrow_mut().connect();
+
+    let lifecycle = element.data().lifecycle.clone();
+    let connection = lifecycle.borrow_mut().connect();
+    let host = Host::new(
+        element.deref().clone(),
+        element.data().reflecting.clone(),
+        element.data().internals.clone(),
+        element.data().shadow_root.as_ref().map(|shadow_root| {
+            let js_ref: &JsValue = shadow_root.as_ref();
+
+            js_ref.clone()
+        }),
+        lifecycle.clone(),
+        connection,
+    );
+    let attributes_changed = AttributesChanged {
+        director: director.clone(),
+    };
+    let props_changed = PropsChanged::new(props_slot, options.on_props_type_error);
+    let (vdoms, abort_handle) = abortable(init(&host, attributes_changed, props_changed));
+
+    // Errors that occurred before initialization go to the error hook if the component did not
+    // subscribe to them.
+    {
+        let mut director = director.borrow_mut();
+
+        if !director.errors_subscribed {
+            for error in director.errors.drain(..) {
+                (options.on_attribute_error)(&error);
+            }
+        }
+    }
+
+    element.data().abort_handle.replace(Some(abort_handle));
+
+    let document = element
+        .owner_document()
+        .try_into()
+        .expect("Guise only supports HTML documents");
+    let backend = ArwaBackend::for_container(document, &container);
+    let js_ref: &JsValue = element.as_ref();
+    let target = js_ref.unchecked_ref::<DynamicElement>().clone();
+
+    spawn_local(render(
+        backend,
+        container,
+        vdoms,
+        element.data().last_vdom.clone(),
+        lifecycle,
+        options.hydrate.then_some(options.on_hydration_mismatch),
+        move |on_rendered| on_rendered(&target),
+    ));
+}
+
+/// Renders the `vdoms` of a connection into the `container` (and renders the [Component]s in the
+/// last vdom again when their state is updated), until the `vdoms` stream ends or is aborted.
+///
+/// If nothing was rendered into the container yet and `hydrate` is set, the first vdom hydrates
+/// the container's existing content and mismatches are reported to `hydrate`. The `on_rendered`
+/// callbacks of the rendered vdoms are passed to `call_on_rendered`.
+async fn render<B, S, F>(
+    backend: B,
+    container: B::Node,
+    mut vdoms: S,
+    last_vdom: Rc<RefCell<Option<VDom>>>,
+    lifecycle: Rc<RefCell<LifecycleDirector>>,
+    hydrate: Option<fn(&HydrationMismatch)>,
+    call_on_rendered: F,
+) where
+    B: Backend,
+    S: Stream<Item = VDom> + Unpin,
+    F: Fn(OnRendered),
+{
+    let mut rendered = false;
+
+    // The components rendered by the patches below report their state updates to the watcher.
+    let watcher = ComponentWatcher::new();
+
+    loop {
+        // Wait for either a new vdom, or a state update in one of the components in the last
+        // vdom.
+        let update = poll_fn(|cx| {
+            if let Poll::Ready(new) = vdoms.poll_next_unpin(cx) {
+                return Poll::Ready(RenderUpdate::Render(new));
+            }
+
+            watcher.poll_updated(cx).map(|()| RenderUpdate::Components)
+        })
+        .await;
+
+        let mut new = match update {
+            RenderUpdate::Render(Some(new)) => new,
+            RenderUpdate::Render(None) => break,
+            RenderUpdate::Components => {
+                let ((), forwarded) = watcher.watch(|| {
+                    collect_on_rendered(|| {
+                        if let Some(last_vdom) = last_vdom.borrow_mut().as_mut() {
+                            patch_components(&backend, &container, last_vdom);
+                        }
+                    })
+                });
+
+                forwarded.into_iter().for_each(&call_on_rendered);
+
+                continue;
+            }
+        };
+
+        let mut last_vdom = last_vdom.borrow_mut();
+
+        // The `on_rendered` callbacks of the memos and components that get rendered by the patch
+        // are called after the callback of the new vdom itself.
+        let ((), forwarded) = watcher.watch(|| {
+            collect_on_rendered(|| {
+                if let Some(old) = last_vdom.take() {
+                    patch_dom(&backend, &container, old, &mut new);
+                } else if let Some(on_hydration_mismatch) = hydrate {
+                    hydrate_dom(&backend, &container, &mut new, |mismatch| {
+                        on_hydration_mismatch(&mismatch)
+                    });
+                } else {
+                    patch_dom(&backend, &container, VDom::new(), &mut new);
+                }
+            })
         });
-    })
-    .disconnected_callback(disconnected_callback::<A, E>)
-    .attribute_changed_callback(A::OBSERVED, attribute_changed_callback::<A, E>);
 
-    registry.register(name, descriptor);
+        new.on_rendered
+            .take()
+            .into_iter()
+            .chain(forwarded)
+            .for_each(&call_on_rendered);
+
+        // Note: this drops the previous vdom (if any), which should abort all old sink tasks.
+        *last_vdom = Some(new);
+
+        if !rendered {
+            rendered = true;
+            lifecycle.borrow_mut().dispatch(LifecycleEvent::FirstRender);
+        }
+    }
+}
+
+enum RenderUpdate {
+    /// The next item of the element's vdom stream.
+    Render(Option<VDom>),
+    /// The state of one or more [Component]s in the last rendered vdom was updated.
+    Components,
 }
 
-fn disconnected_callback<A, E>(element: &CustomElement<ComponentData<A>, E>)
+fn disconnected_callback<A, E>(
+    element: &CustomElement<ComponentData<A>, E>,
+    options: &RegisterOptions,
+) where
+    A: Attributes + 'static,
+    E: Clone + 'static,
+{
+    match options.disconnect_policy {
+        DisconnectPolicy::Reinitialize => tear_down(element),
+        DisconnectPolicy::KeepAlive(grace_period) => {
+            let data = element.data();
+            let disconnect = data.disconnect_count.get() + 1;
+
+            data.disconnect_count.set(disconnect);
+            data.parked.set(true);
+
+            let element = element.clone();
+            let handler = Closure::once_into_js(move || {
+                let data = element.data();
+
+                // Only tear down if the element was not reconnected (or disconnected again, in
+                // which case a later timeout is responsible) in the meantime.
+                if data.parked.get() && data.disconnect_count.get() == disconnect {
+                    data.parked.set(false);
+                    tear_down(&element);
+                }
+            });
+
+            set_timeout(&handler, grace_period.as_millis().min(i32::MAX as u128) as i32);
+        }
+    }
+}
+
+fn tear_down<A, E>(element: &CustomElement<ComponentData<A>, E>)
 where
-    A: Default + 'static,
+    A: Attributes + 'static,
 {
-    if let Some(abort_handle) = element.data().abort_handle.borrow_mut().take() {
-        abort_handle.abort();
+    let data = element.data();
+
+    data.props_slot.borrow_mut().disconnect();
+
+    if let Some(form) = data.form.as_ref() {
+        form.disconnect();
     }
 
-    let mut director = element.data().attribute_change_director.borrow_mut();
+    end_connection(
+        &data.attribute_change_director,
+        &data.lifecycle,
+        data.abort_handle.take(),
+    );
+}
 
-    if let Some(waker) = director.waker.take() {
-        waker.wake();
+/// Ends the current connection of a component: ends its attribute and lifecycle streams, and
+/// aborts its vdom stream.
+///
+/// The vdom stream is aborted immediately (rather than when the render task is next polled), so
+/// that the render task of this connection can't observe the state of a connection that starts
+/// in the same task (e.g. when the element is moved with `appendChild`). The `Disconnected`
+/// lifecycle event is dispatched first, so that tasks the component spawned still observe it.
+fn end_connection<A>(
+    director: &RefCell<AttributeChangeDirector<A>>,
+    lifecycle: &RefCell<LifecycleDirector>,
+    abort_handle: Option<AbortHandle>,
+) where
+    A: Attributes,
+{
+    director.borrow_mut().disconnect();
+    lifecycle.borrow_mut().disconnect();
+
+    if let Some(abort_handle) = abort_handle {
+        abort_handle.abort();
+    }
+}
+
+fn adopted_callback<A, E>(element: &CustomElement<ComponentData<A>, E>)
+where
+    A: Attributes + 'static,
+{
+    // The shadow root's constructed style sheets can only be adopted in the document they were
+    // constructed for, so the sheets are adopted again with instances for the new document.
+    if let Some(adopted_style_sheets) = &element.data().adopted_style_sheets {
+        adopted_style_sheets.reapply();
     }
 
-    director.attributes = A::default();
-    director.disconnected = true;
+    element
+        .data()
+        .lifecycle
+        .borrow_mut()
+        .dispatch(LifecycleEvent::Adopted);
 }
 
 fn attribute_changed_callback<A, E>(
     element: &CustomElement<ComponentData<A>, E>,
     change: AttributeChange,
+    options: &RegisterOptions,
 ) where
     A: Attributes + 'static,
 {
     let mut director = element.data().attribute_change_director.borrow_mut();
 
-    director
-        .attributes
-        .update(&change.attribute_name, change.new_value);
+    director.change(
+        &change.attribute_name,
+        change.new_value,
+        options.on_attribute_error,
+    );
+
+    // Don't wake the component for changes it made to its own host element (see [Host]).
+    if element.data().reflecting.get() {
+        return;
+    }
 
     // Wake the attributes changed task. If there's no waker, assume the task is already
     // awake and queued to be polled. Note that if multiple attributes change at once, this
     // callback should be queued as multiple consecutive micro-tasks; only the first will wake
     // the attributes changed task, and the browser will enqueue it after all callback tasks, so
     // it will run only once.
-    if let Some(waker) = director.waker.take() {
-        waker.wake();
+    director.wake();
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::{block_on, LocalPool};
+    use futures::task::LocalSpawnExt;
+
+    use crate::backend::{MemoryBackend, MemoryNode};
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct LabelAttributes {
+        label: String,
+    }
+
+    impl Attributes for LabelAttributes {
+        const OBSERVED: &'static [Name] = &[name!("label")];
+
+        type Diff = ();
+
+        fn update(&mut self, _name: &Name, value: Option<String>) -> Result<(), AttributeError> {
+            self.label = value.unwrap_or_default();
+
+            Ok(())
+        }
+
+        fn record_change(_diff: &mut (), _name: &Name) {}
+    }
+
+    /// The connection state of a component's element, which renders its `label` attribute.
+    struct TestElement {
+        director: Rc<RefCell<AttributeChangeDirector<LabelAttributes>>>,
+        lifecycle: Rc<RefCell<LifecycleDirector>>,
+        last_vdom: Rc<RefCell<Option<VDom>>>,
+        abort_handle: Option<AbortHandle>,
+        container: MemoryNode,
+    }
+
+    impl TestElement {
+        fn new() -> Self {
+            TestElement {
+                director: Rc::new(RefCell::new(AttributeChangeDirector::new())),
+                lifecycle: Rc::new(RefCell::new(LifecycleDirector::new())),
+                last_vdom: Rc::new(RefCell::new(None)),
+                abort_handle: None,
+                container: MemoryNode::container(),
+            }
+        }
+
+        /// Like `connected_callback`; returns the lifecycle stream of the new connection.
+        fn connect(&mut self, pool: &LocalPool) -> Lifecycle {
+            self.director.borrow_mut().disconnected = false;
+
+            let connection = self.lifecycle.borrow_mut().connect();
+            let lifecycle = self.lifecycle.borrow_mut().subscribe(connection);
+            let attributes_changed = AttributesChanged {
+                director: self.director.clone(),
+            };
+            let (vdoms, abort_handle) = abortable(attributes_changed.map(|attributes| {
+                let mut vdom = VDom::new();
+
+                vdom.text(&attributes.label);
+
+                vdom
+            }));
+
+            self.abort_handle = Some(abort_handle);
+
+            pool.spawner()
+                .spawn_local(render(
+                    MemoryBackend::new(),
+                    self.container.clone(),
+                    vdoms,
+                    self.last_vdom.clone(),
+                    self.lifecycle.clone(),
+                    None,
+                    |_| {},
+                ))
+                .unwrap();
+
+            lifecycle
+        }
+
+        /// Like `tear_down`.
+        fn disconnect(&mut self) {
+            end_connection(&self.director, &self.lifecycle, self.abort_handle.take());
+        }
+
+        /// Like `attribute_changed_callback`.
+        fn set_label(&self, label: &str) {
+            let mut director = self.director.borrow_mut();
+
+            director.change(&name!("label"), Some(label.to_string()), |_| {});
+            director.wake();
+        }
+    }
+
+    #[test]
+    fn reconnect_in_the_same_task() {
+        let mut pool = LocalPool::new();
+        let mut element = TestElement::new();
+
+        element.connect(&pool);
+        element.set_label("first");
+        pool.run_until_stalled();
+
+        assert_eq!(format!("{:?}", element.container), "first");
+
+        // Move the element: it is disconnected and connected again before the render task of the
+        // first connection gets to run.
+        element.disconnect();
+
+        let lifecycle = element.connect(&pool);
+
+        pool.run_until_stalled();
+        element.set_label("second");
+        pool.run_until_stalled();
+
+        assert_eq!(format!("{:?}", element.container), "second");
+
+        element.disconnect();
+
+        // The render task of the first connection did not dispatch its `FirstRender` event into
+        // the second connection.
+        assert_eq!(
+            block_on(lifecycle.collect::<Vec<_>>()),
+            [
+                LifecycleEvent::Connected,
+                LifecycleEvent::FirstRender,
+                LifecycleEvent::Disconnected
+            ]
+        );
     }
 }
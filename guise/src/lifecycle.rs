@@ -0,0 +1,145 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::rc::{Rc, Weak};
+use std::task::{Context, Poll, Waker};
+
+use futures::Stream;
+
+/// An event in the lifecycle of a component's host element (see [Host::lifecycle]).
+///
+/// [Host::lifecycle]: crate::Host::lifecycle
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LifecycleEvent {
+    /// The element was moved to a new document.
+    ///
+    /// An element is disconnected before it is adopted into a new document, so this event is
+    /// reported at the start of the next connection, before [LifecycleEvent::Connected].
+    Adopted,
+    /// The element was connected.
+    Connected,
+    /// The first [VDom](crate::VDom) since the element was connected was rendered.
+    FirstRender,
+    /// The element was disconnected; this is the last event of a [Lifecycle] stream.
+    ///
+    /// The component's vdom stream is dropped as soon as the element is disconnected, so a
+    /// [Lifecycle] stream that the vdom stream consumes does not observe this event; consume it in
+    /// a task that the component spawns (e.g. with `spawn_local`) instead.
+    Disconnected,
+}
+
+struct Subscriber {
+    queue: VecDeque<LifecycleEvent>,
+    ended: bool,
+    waker: Option<Waker>,
+}
+
+/// Dispatches the lifecycle events of an element to its [Lifecycle] streams.
+pub(crate) struct LifecycleDirector {
+    connection: u64,
+    connected: bool,
+    /// The events of the current connection, which are replayed to new subscribers; while the
+    /// element is disconnected, holds the events for the next connection.
+    history: Vec<LifecycleEvent>,
+    subscribers: Vec<Weak<RefCell<Subscriber>>>,
+}
+
+impl LifecycleDirector {
+    pub(crate) fn new() -> Self {
+        LifecycleDirector {
+            connection: 0,
+            connected: false,
+            history: Vec::new(),
+            subscribers: Vec::new(),
+        }
+    }
+
+    /// Starts a new connection and returns its ID.
+    pub(crate) fn connect(&mut self) -> u64 {
+        self.connection += 1;
+        self.connected = true;
+        self.dispatch(LifecycleEvent::Connected);
+
+        self.connection
+    }
+
+    /// Ends the current connection, which ends all current [Lifecycle] streams.
+    pub(crate) fn disconnect(&mut self) {
+        self.dispatch(LifecycleEvent::Disconnected);
+        self.connected = false;
+        self.history.clear();
+        self.subscribers.clear();
+    }
+
+    pub(crate) fn dispatch(&mut self, event: LifecycleEvent) {
+        self.history.push(event);
+
+        self.subscribers.retain(|subscriber| {
+            if let Some(subscriber) = subscriber.upgrade() {
+                let mut subscriber = subscriber.borrow_mut();
+
+                subscriber.queue.push_back(event);
+
+                if event == LifecycleEvent::Disconnected {
+                    subscriber.ended = true;
+                }
+
+                if let Some(waker) = subscriber.waker.take() {
+                    waker.wake();
+                }
+
+                true
+            } else {
+                false
+            }
+        });
+    }
+
+    /// Returns a stream of the events of the connection with the given ID; the stream ends
+    /// immediately if that connection has already ended.
+    pub(crate) fn subscribe(&mut self, connection: u64) -> Lifecycle {
+        let current = self.connected && self.connection == connection;
+        let subscriber = Rc::new(RefCell::new(Subscriber {
+            queue: if current {
+                self.history.iter().copied().collect()
+            } else {
+                VecDeque::new()
+            },
+            ended: !current,
+            waker: None,
+        }));
+
+        if current {
+            self.subscribers.push(Rc::downgrade(&subscriber));
+        }
+
+        Lifecycle { subscriber }
+    }
+}
+
+/// A stream of the lifecycle events of a component's host element during a single connection.
+///
+/// Replays the events of the connection that occurred before the stream was created (so the
+/// stream always starts with [LifecycleEvent::Connected], possibly preceded by
+/// [LifecycleEvent::Adopted]), and ends after [LifecycleEvent::Disconnected].
+pub struct Lifecycle {
+    subscriber: Rc<RefCell<Subscriber>>,
+}
+
+impl Stream for Lifecycle {
+    type Item = LifecycleEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut subscriber = self.subscriber.borrow_mut();
+
+        if let Some(event) = subscriber.queue.pop_front() {
+            Poll::Ready(Some(event))
+        } else if subscriber.ended {
+            Poll::Ready(None)
+        } else {
+            subscriber.waker = Some(cx.waker().clone());
+
+            Poll::Pending
+        }
+    }
+}
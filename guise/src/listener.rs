@@ -59,3 +59,17 @@ where
         }
     }
 }
+
+/// Options for the event listener through which an event sink receives its events (see
+/// `ElementBuilder::sink_event_with_options`).
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct ListenerOptions {
+    /// Receive the events during the capture phase, before they reach the descendants of the
+    /// element.
+    pub capture: bool,
+    /// Promise not to cancel the events, which lets the browser e.g. scroll without waiting for
+    /// `wheel` or `touchmove` events to be handled.
+    pub passive: bool,
+    /// Only receive the first event; the listener is removed after it.
+    pub once: bool,
+}
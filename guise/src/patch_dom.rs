@@ -1,119 +1,480 @@
-use std::cmp::min;
-use std::convert::TryFrom;
+use std::collections::{HashMap, HashSet};
 
-use arwa::collection::Sequence;
-use arwa::dom::{
-    CharacterData, ChildNode, Document, DynamicChildNode, DynamicElement, Element as ArwaElement,
-    Name, ParentNode, Text,
-};
-use arwa::html::{HtmlDocument, HtmlInputElement};
+use arwa::dom::DynamicElement;
 
-use crate::sink_spawner::SinkSpawner;
-use crate::vdom::{Attribute, Element, Node, VDom};
+use crate::backend::Backend;
 use crate::element_ref::RawElementRef;
+use crate::scoped_style::ScopedStyle;
+use crate::sink_spawner::SinkSpawner;
+use crate::util::warn;
+use crate::vdom::{Attribute, Element, Node, Property, Props, VDom};
 
-pub fn patch_dom<E>(document: &HtmlDocument, container: &E, mut old: VDom, new: &mut VDom)
+/// Patches the children of the `container` node so that they match the `new` virtual DOM, given
+/// that they currently match the `old` virtual DOM.
+pub fn patch_dom<B>(backend: &B, container: &B::Node, mut old: VDom, new: &mut VDom)
 where
-    E: ParentNode,
+    B: Backend,
 {
+    let old_dom = backend.child_nodes(container);
+    let scope = vdom_scope(backend, new, None);
+
     old.with_nodes_mut(|old_nodes| {
         new.with_nodes_mut(|new_nodes| {
-            patch_children(&document, container, old_nodes, new_nodes);
+            patch_children(
+                backend, container, &old_dom, None, old_nodes, new_nodes, scope,
+            );
         });
     });
 }
 
-fn patch_node(
-    document: &HtmlDocument,
-    node: &DynamicChildNode,
+/// Returns the scoped style for the elements of the `vdom`: the vdom's own scoped style, or the
+/// `inherited` scoped style of the enclosing vdom. Adds the style to the document, if necessary.
+pub(crate) fn vdom_scope<B>(
+    backend: &B,
+    vdom: &VDom,
+    inherited: Option<ScopedStyle>,
+) -> Option<ScopedStyle>
+where
+    B: Backend,
+{
+    let scope = vdom.scoped_style().or(inherited);
+
+    if let Some(scope) = scope {
+        backend.add_style(scope.attribute(), scope.css());
+    }
+
+    scope
+}
+
+/// The number of DOM nodes that the (already rendered) `node` occupies.
+///
+/// Text and element nodes occupy a single DOM node; a fragment occupies the DOM nodes of its
+/// children and a memo or component occupies the DOM nodes of its rendered nodes.
+fn dom_len(node: &Node) -> usize {
+    match node {
+        Node::Text(_) | Node::Element(_) => 1,
+        Node::Fragment(fragment) => fragment.children().iter().map(dom_len).sum(),
+        Node::Memo(memo) => memo
+            .rendered()
+            .map(|vdom| vdom.with_nodes(|nodes| nodes.iter().map(dom_len).sum::<usize>()))
+            .unwrap_or(0),
+        Node::Component(component) => component
+            .instance()
+            .rendered()
+            .map(|vdom| vdom.with_nodes(|nodes| nodes.iter().map(dom_len).sum::<usize>()))
+            .unwrap_or(0),
+    }
+}
+
+/// Patches a compatible `old` node into the `new` node and returns the DOM nodes that the `new`
+/// node occupies.
+///
+/// The `old_dom` nodes are the DOM nodes that the `old` node currently occupies; DOM nodes that get
+/// added are inserted before the `reference` node (or appended if there is no reference node).
+fn patch_node<B>(
+    backend: &B,
+    parent: &B::Node,
+    old_dom: &[B::Node],
+    reference: Option<&B::Node>,
     old: &mut Node,
-    mut new: &mut Node,
-) {
-    match (old, &mut new) {
+    new: &mut Node,
+    scope: Option<ScopedStyle>,
+) -> Vec<B::Node>
+where
+    B: Backend,
+{
+    match (old, new) {
         (Node::Text(old), Node::Text(new)) => {
-            if old != new {
-                let text = Text::try_from(node.clone())
-                    .expect("actual node type does not match v-node type");
-
-                text.set_data(new);
+            if *old != *new {
+                backend.set_text(&old_dom[0], *new);
             }
 
-            return;
+            old_dom.to_vec()
         }
         (Node::Element(old), Node::Element(new)) => {
-            let element = DynamicElement::try_from(node.clone())
-                .expect("actual node type does not match v-node type");
+            let node = &old_dom[0];
+
+            patch_attributes(backend, node, old.attributes(), new.attributes());
+            patch_children(
+                backend,
+                node,
+                &backend.child_nodes(node),
+                None,
+                old.children_mut(),
+                new.children_mut(),
+                scope,
+            );
+            patch_properties(backend, node, new.properties());
+            patch_props(backend, node, old.props(), new.props());
+            attach(backend, node, new);
+
+            old_dom.to_vec()
+        }
+        (Node::Fragment(old), Node::Fragment(new)) => patch_children(
+            backend,
+            parent,
+            old_dom,
+            reference,
+            old.children_mut(),
+            new.children_mut(),
+            scope,
+        ),
+        (Node::Memo(old), Node::Memo(new)) => {
+            if new.deps_eq(old) {
+                new.reuse(old);
+
+                return old_dom.to_vec();
+            }
 
-            if old.tag_name() == new.tag_name() && old.is() == new.is() {
-                patch_attributes(&element, old.attributes(), new.attributes());
-                patch_children(document, &element, old.children_mut(), new.children_mut());
-                spawn_sinks(&element, new.sink_spawners_mut());
-                set_ref_anchors(&element, new.element_refs_mut());
+            let old = old.rendered_mut();
+            let new = new.rendered_mut();
+            let scope = vdom_scope(backend, new, scope);
+
+            old.with_nodes_mut(|old_nodes| {
+                new.with_nodes_mut(|new_nodes| {
+                    patch_children(
+                        backend, parent, old_dom, reference, old_nodes, new_nodes, scope,
+                    )
+                })
+            })
+        }
+        (Node::Component(old), Node::Component(new)) => {
+            // The new instance takes over the state of the old instance and is rendered with the
+            // new props.
+            new.instance_mut().adopt(old.instance_mut());
+
+            let old = old.instance_mut().rendered_mut();
+            let new = new.instance_mut().rendered_mut();
+            let scope = vdom_scope(backend, new, scope);
+
+            old.with_nodes_mut(|old_nodes| {
+                new.with_nodes_mut(|new_nodes| {
+                    patch_children(
+                        backend, parent, old_dom, reference, old_nodes, new_nodes, scope,
+                    )
+                })
+            })
+        }
+        (_, new) => {
+            // Callers only patch compatible nodes (see `Node::is_compatible`); should the nodes be
+            // incompatible regardless, the old DOM nodes are replaced with fresh nodes.
+            let fresh = fresh_nodes(backend, new, scope);
+            let reference = old_dom.first().or(reference);
+
+            for node in fresh.iter() {
+                insert_before(backend, parent, node, reference);
+            }
 
-                return;
+            for node in old_dom {
+                backend.remove(node);
             }
+
+            fresh
         }
-        _ => (),
     }
-
-    replace_fresh(document, node, new);
 }
 
-fn patch_children<E>(document: &HtmlDocument, parent: &E, old: &mut [Node], new: &mut [Node])
+/// Patches the `old` nodes into the `new` nodes and returns the DOM nodes that the `new` nodes
+/// occupy, in order.
+///
+/// The `old_dom` nodes are the DOM nodes that the `old` nodes currently occupy; these are children
+/// of the `parent` node, followed by the `end` node (or at the end of the `parent`'s children if
+/// there is no `end` node).
+fn patch_children<B>(
+    backend: &B,
+    parent: &B::Node,
+    old_dom: &[B::Node],
+    end: Option<&B::Node>,
+    old: &mut [Node],
+    new: &mut [Node],
+    scope: Option<ScopedStyle>,
+) -> Vec<B::Node>
 where
-    E: ParentNode,
+    B: Backend,
 {
-    let children = parent.child_nodes();
-    let overlap = min(old.len(), new.len());
+    // The range of DOM nodes that each old node occupies.
+    let mut old_ranges = Vec::with_capacity(old.len());
+    let mut offset = 0;
 
-    for i in 0..overlap {
-        let node = children.get(i as u32).unwrap();
+    for node in old.iter() {
+        let len = dom_len(node);
 
-        patch_node(document, &node, &mut old[i], &mut new[i])
+        old_ranges.push(offset..offset + len);
+        offset += len;
+    }
+
+    // Keyed nodes are matched by key, unkeyed nodes are matched in order against the unkeyed nodes
+    // in the old children. Note that keys are references into the old vdom's arena, not borrows
+    // of `old`, so we can still mutate `old` while holding on to `old_keyed`.
+    let mut old_keyed = HashMap::new();
+    let mut old_unkeyed = Vec::new();
+
+    for (i, node) in old.iter().enumerate() {
+        if let Some(key) = node.key() {
+            old_keyed.entry(key).or_insert(i);
+        } else {
+            old_unkeyed.push(i);
+        }
     }
 
-    let remove_count = old.len() - overlap;
+    let mut old_unkeyed = old_unkeyed.into_iter();
+    let mut sources: Vec<Option<usize>> = Vec::with_capacity(new.len());
+    let mut targets: Vec<Option<usize>> = vec![None; old.len()];
+
+    // Only used to detect duplicate keys in debug builds.
+    let mut new_keys = HashSet::new();
+
+    for (i, node) in new.iter().enumerate() {
+        let source = if let Some(key) = node.key() {
+            if cfg!(debug_assertions) && !new_keys.insert(key) {
+                warn(&format!(
+                    "duplicate key `{}` among siblings; only the first node with this key is \
+                     matched by key",
+                    key
+                ));
+            }
+
+            old_keyed.remove(key)
+        } else {
+            old_unkeyed.next()
+        };
 
-    for _ in 0..remove_count {
-        if let Some(child) = children.last() {
-            child.disconnect();
+        // Only reuse the old node if it can be patched in place; otherwise we create a fresh node
+        // and the old node gets removed.
+        let source = source.filter(|j| old[*j].is_compatible(node));
+
+        if let Some(j) = source {
+            targets[j] = Some(i);
         }
+
+        sources.push(source);
     }
 
-    if new.len() > overlap {
-        for i in overlap..new.len() {
-            append_fresh(document, parent, &mut new[i])
+    // Patch matched nodes in place and remove unmatched nodes. We do this in the old order, so that
+    // the DOM node that follows an old node is still in place when that old node is patched, and
+    // can serve as the reference node for any DOM nodes the patch inserts.
+    let mut patched: Vec<Option<Vec<B::Node>>> = (0..new.len()).map(|_| None).collect();
+
+    for (j, range) in old_ranges.iter().enumerate() {
+        let dom = &old_dom[range.clone()];
+
+        if let Some(i) = targets[j] {
+            let reference = old_dom.get(range.end).or(end);
+
+            patched[i] = Some(patch_node(
+                backend,
+                parent,
+                dom,
+                reference,
+                &mut old[j],
+                &mut new[i],
+                scope,
+            ));
+        } else {
+            for node in dom {
+                backend.remove(node);
+            }
         }
     }
+
+    // Nodes that are part of the longest increasing subsequence of old positions are already in
+    // the correct relative order and don't have to be moved; all other matched nodes get moved
+    // and all unmatched new nodes get inserted. We work backwards, so that the node after the
+    // current node is always already in its final position and can serve as the reference node
+    // for insertion.
+    let stable = longest_increasing_subsequence(&sources);
+    let mut next: Option<B::Node> = end.cloned();
+    let mut result = Vec::with_capacity(new.len());
+
+    for i in (0..new.len()).rev() {
+        let nodes = if let Some(nodes) = patched[i].take() {
+            if !stable[i] {
+                for node in nodes.iter() {
+                    insert_before(backend, parent, node, next.as_ref());
+                }
+            }
+
+            nodes
+        } else {
+            let nodes = fresh_nodes(backend, &mut new[i], scope);
+
+            for node in nodes.iter() {
+                insert_before(backend, parent, node, next.as_ref());
+            }
+
+            nodes
+        };
+
+        if let Some(first) = nodes.first() {
+            next = Some(first.clone());
+        }
+
+        result.extend(nodes.into_iter().rev());
+    }
+
+    result.reverse();
+
+    result
 }
 
-fn patch_attributes(element: &DynamicElement, old: &[Attribute], new: &[Attribute]) {
-    // Note: special treatment for the "checked" attribute, which maps to the `defaultChecked`
-    // property, rather than the `checked` property. We explicitly set the `checked` property to
-    // `true` whenever a "checked" attribute is added, and we set the `checked` property to `false`
-    // whenever a "checked" attribute is removed.
-    fn maybe_check(element: &DynamicElement, attr: &Name) {
-        if unicase::eq_ascii(attr.as_ref(), "checked") {
-            if let Ok(input) = TryInto::<HtmlInputElement>::try_into(element.clone()) {
-                input.set_checked(true);
+/// Renders the components in the `vdom` whose state was updated since they were last rendered
+/// again, and patches their DOM nodes, given that the children of the `container` node currently
+/// match the `vdom`.
+pub(crate) fn patch_components<B>(backend: &B, container: &B::Node, vdom: &mut VDom)
+where
+    B: Backend,
+{
+    let dom = backend.child_nodes(container);
+    let scope = vdom_scope(backend, vdom, None);
+
+    vdom.with_nodes_mut(|nodes| {
+        patch_dirty_components(backend, container, &dom, None, nodes, scope);
+    });
+}
+
+fn patch_dirty_components<B>(
+    backend: &B,
+    parent: &B::Node,
+    dom: &[B::Node],
+    end: Option<&B::Node>,
+    nodes: &mut [Node],
+    scope: Option<ScopedStyle>,
+) where
+    B: Backend,
+{
+    let mut offset = 0;
+
+    for node in nodes.iter_mut() {
+        let range = offset..offset + dom_len(node);
+        let reference = dom.get(range.end).or(end);
+
+        match node {
+            Node::Text(_) => {}
+            Node::Element(element) => {
+                let node = &dom[range.start];
+
+                patch_dirty_components(
+                    backend,
+                    node,
+                    &backend.child_nodes(node),
+                    None,
+                    element.children_mut(),
+                    scope,
+                );
+            }
+            Node::Fragment(fragment) => patch_dirty_components(
+                backend,
+                parent,
+                &dom[range.clone()],
+                reference,
+                fragment.children_mut(),
+                scope,
+            ),
+            Node::Memo(memo) => {
+                let vdom = memo.rendered_mut();
+                let scope = vdom_scope(backend, vdom, scope);
+
+                vdom.with_nodes_mut(|nodes| {
+                    patch_dirty_components(
+                        backend,
+                        parent,
+                        &dom[range.clone()],
+                        reference,
+                        nodes,
+                        scope,
+                    )
+                })
+            }
+            Node::Component(component) => {
+                let instance = component.instance_mut();
+
+                if instance.is_dirty() {
+                    let mut old = instance.rerender().unwrap();
+                    let new = instance.rendered_mut();
+                    let scope = vdom_scope(backend, new, scope);
+                    let old_dom = &dom[range.clone()];
+
+                    old.with_nodes_mut(|old| {
+                        new.with_nodes_mut(|new| {
+                            patch_children(backend, parent, old_dom, reference, old, new, scope)
+                        })
+                    });
+                } else {
+                    let vdom = instance.rendered_mut();
+                    let scope = vdom_scope(backend, vdom, scope);
+
+                    vdom.with_nodes_mut(|nodes| {
+                        patch_dirty_components(
+                            backend,
+                            parent,
+                            &dom[range.clone()],
+                            reference,
+                            nodes,
+                            scope,
+                        )
+                    });
+                }
             }
         }
+
+        offset = range.end;
+    }
+}
+
+fn insert_before<B>(backend: &B, parent: &B::Node, node: &B::Node, reference: Option<&B::Node>)
+where
+    B: Backend,
+{
+    if let Some(reference) = reference {
+        backend.insert_before(parent, node, reference);
+    } else {
+        backend.append_child(parent, node);
     }
-    fn maybe_uncheck(element: &DynamicElement, attr: &Name) {
-        if unicase::eq_ascii(attr.as_ref(), "checked") {
-            if let Ok(input) = TryInto::<HtmlInputElement>::try_into(element.clone()) {
-                input.set_checked(false);
+}
+
+/// Marks the entries in `sources` that are part of the longest strictly increasing subsequence of
+/// the `Some` values.
+fn longest_increasing_subsequence(sources: &[Option<usize>]) -> Vec<bool> {
+    // `tails[k]` holds the index (into `sources`) of the smallest tail value of all increasing
+    // subsequences of length `k + 1` found so far; `predecessors[i]` links an entry to the
+    // previous entry in the subsequence it extends.
+    let mut tails: Vec<usize> = Vec::new();
+    let mut predecessors: Vec<Option<usize>> = vec![None; sources.len()];
+
+    for (i, source) in sources.iter().enumerate() {
+        if let Some(value) = *source {
+            let position = tails.partition_point(|t| sources[*t].unwrap() < value);
+
+            if position > 0 {
+                predecessors[i] = Some(tails[position - 1]);
+            }
+
+            if position == tails.len() {
+                tails.push(i);
+            } else {
+                tails[position] = i;
             }
         }
     }
 
-    let attributes = element.attributes();
+    let mut stable = vec![false; sources.len()];
+    let mut current = tails.last().copied();
+
+    while let Some(i) = current {
+        stable[i] = true;
+        current = predecessors[i];
+    }
 
+    stable
+}
+
+fn patch_attributes<B>(backend: &B, element: &B::Node, old: &[Attribute], new: &[Attribute])
+where
+    B: Backend,
+{
     if old.is_empty() {
         for a in new {
-            attributes.set(a.name(), a.value());
-
-            maybe_check(element, a.name());
+            backend.set_attribute(element, a.namespace(), a.name(), a.value());
         }
 
         return;
@@ -121,9 +482,7 @@ fn patch_attributes(element: &DynamicElement, old: &[Attribute], new: &[Attribut
 
     if new.is_empty() {
         for a in old {
-            attributes.remove(a.name());
-
-            maybe_uncheck(element, a.name());
+            backend.remove_attribute(element, a.name().as_ref());
         }
 
         return;
@@ -133,7 +492,7 @@ fn patch_attributes(element: &DynamicElement, old: &[Attribute], new: &[Attribut
     'outer: for a_new in new {
         'inner: for a_old in old {
             if a_new.name() == a_old.name() {
-                if a_new.value() != a_old.value() {
+                if a_new.value() != a_old.value() || a_new.namespace() != a_old.namespace() {
                     break 'inner;
                 }
 
@@ -141,9 +500,7 @@ fn patch_attributes(element: &DynamicElement, old: &[Attribute], new: &[Attribut
             }
         }
 
-        attributes.set(a_new.name(), a_new.value());
-
-        maybe_check(element, a_new.name());
+        backend.set_attribute(element, a_new.namespace(), a_new.name(), a_new.value());
     }
 
     // Remove any old attributes that arnt in the new vdom
@@ -154,52 +511,117 @@ fn patch_attributes(element: &DynamicElement, old: &[Attribute], new: &[Attribut
             }
         }
 
-        attributes.remove(a_old.name());
+        backend.remove_attribute(element, a_old.name().as_ref());
+    }
+}
 
-        maybe_uncheck(element, a_old.name());
+/// Sets any property whose value differs from the live value of the DOM property.
+pub(crate) fn patch_properties<B>(backend: &B, element: &B::Node, properties: &[Property])
+where
+    B: Backend,
+{
+    for p in properties {
+        if !backend.property_eq(element, p.name(), p.value()) {
+            backend.set_property(element, p.name(), p.value());
+        }
     }
 }
 
-fn append_fresh<E>(document: &HtmlDocument, parent: &E, node: &mut Node)
+fn patch_props<B>(backend: &B, element: &B::Node, old: Option<&Props>, new: Option<&Props>)
 where
-    E: ParentNode,
+    B: Backend,
 {
-    match node {
-        Node::Text(text) => parent.append_child(&document.create_text(text)),
-        Node::Element(element) => parent.append_child(&fresh_element(document, element)),
+    if let Some(new) = new {
+        if !old.map(|old| old.eq(new)).unwrap_or(false) {
+            backend.set_props(element, new.value());
+        }
     }
 }
 
-fn replace_fresh(document: &HtmlDocument, target: &DynamicChildNode, node: &mut Node) {
+/// Creates the DOM nodes for the `node`; elements are stamped with the attribute of the `scope`.
+pub(crate) fn fresh_nodes<B>(
+    backend: &B,
+    node: &mut Node,
+    scope: Option<ScopedStyle>,
+) -> Vec<B::Node>
+where
+    B: Backend,
+{
     match node {
-        Node::Text(text) => target.replace_with(&document.create_text(text)),
-        Node::Element(element) => target.replace_with(&fresh_element(document, element)),
+        Node::Text(text) => vec![backend.create_text(text)],
+        Node::Element(element) => vec![fresh_element(backend, element, scope)],
+        Node::Fragment(fragment) => fragment
+            .children_mut()
+            .iter_mut()
+            .flat_map(|n| fresh_nodes(backend, n, scope))
+            .collect(),
+        Node::Memo(memo) => fresh_rendered_nodes(backend, memo.rendered_mut(), scope),
+        Node::Component(component) => {
+            fresh_rendered_nodes(backend, component.instance_mut().rendered_mut(), scope)
+        }
     }
 }
 
-fn fresh_element(document: &HtmlDocument, element: &mut Element) -> DynamicElement {
-    let e = if let Some(is) = element.is() {
-        document.create_customized_element(element.tag_name(), is)
-    } else {
-        document.create_element(element.tag_name())
-    };
+/// Creates the DOM nodes for the nodes rendered by a memo or component.
+fn fresh_rendered_nodes<B>(backend: &B, vdom: &mut VDom, scope: Option<ScopedStyle>) -> Vec<B::Node>
+where
+    B: Backend,
+{
+    let scope = vdom_scope(backend, vdom, scope);
+
+    vdom.with_nodes_mut(|nodes| {
+        nodes
+            .iter_mut()
+            .flat_map(|n| fresh_nodes(backend, n, scope))
+            .collect()
+    })
+}
 
-    let attributes = e.attributes();
+fn fresh_element<B>(backend: &B, element: &mut Element, scope: Option<ScopedStyle>) -> B::Node
+where
+    B: Backend,
+{
+    let e = backend.create_element(element.namespace(), element.tag_name(), element.is());
 
     for a in element.attributes() {
-        attributes.set(a.name(), a.value());
+        backend.set_attribute(&e, a.namespace(), a.name(), a.value());
+    }
+
+    if let Some(scope) = scope {
+        backend.set_attribute(&e, None, &scope.attribute_name(), "");
     }
 
     for node in element.children_mut() {
-        append_fresh(document, &e, node);
+        for child in fresh_nodes(backend, node, scope) {
+            backend.append_child(&e, &child);
+        }
     }
 
-    spawn_sinks(&e, element.sink_spawners_mut());
-    set_ref_anchors(&e, element.element_refs_mut());
+    for p in element.properties() {
+        backend.set_property(&e, p.name(), p.value());
+    }
+
+    if let Some(props) = element.props() {
+        backend.set_props(&e, props.value());
+    }
+
+    attach(backend, &e, element);
 
     e
 }
 
+/// Spawns the element's sink tasks and anchors its element refs, if the backend is backed by an
+/// actual DOM.
+pub(crate) fn attach<B>(backend: &B, node: &B::Node, element: &mut Element)
+where
+    B: Backend,
+{
+    if let Some(dom_element) = backend.dom_element(node) {
+        spawn_sinks(&dom_element, element.sink_spawners_mut());
+        set_ref_anchors(&dom_element, element.element_refs_mut());
+    }
+}
+
 fn spawn_sinks(element: &DynamicElement, spawners: &mut [SinkSpawner]) {
     for spawner in spawners {
         spawner.spawn(&element);
@@ -211,3 +633,334 @@ fn set_ref_anchors(element: &DynamicElement, element_refs: &mut [RawElementRef])
         element_ref.set_element(element.clone());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+    use std::task::Context;
+
+    use arwa::dom::name;
+    use futures::task::noop_waker;
+
+    use crate::backend::{MemoryBackend, MemoryNode};
+    use crate::component::Component;
+    use crate::vdom::Namespace;
+    use crate::view_model::{ComponentWatcher, Updater};
+
+    use super::*;
+
+    fn keyed_list(keys: &[&str]) -> VDom {
+        let mut vdom = VDom::new();
+
+        for key in keys {
+            vdom.child(name!("li"), |mut li| {
+                li.key(key);
+                li.text(key);
+            });
+        }
+
+        vdom
+    }
+
+    fn find_child(container: &MemoryNode, text: &str) -> MemoryNode {
+        container
+            .children()
+            .into_iter()
+            .find(|child| child.children()[0].text().as_deref() == Some(text))
+            .unwrap()
+    }
+
+    #[test]
+    fn longest_increasing_subsequence_skips_unmatched_entries() {
+        let stable = longest_increasing_subsequence(&[Some(2), Some(0), None, Some(1), Some(3)]);
+
+        assert_eq!(stable, vec![false, true, false, true, true]);
+    }
+
+    #[test]
+    fn longest_increasing_subsequence_of_reversed_sources() {
+        let stable = longest_increasing_subsequence(&[Some(3), Some(2), Some(1), Some(0)]);
+
+        assert_eq!(stable.iter().filter(|stable| **stable).count(), 1);
+    }
+
+    #[test]
+    fn reorders_keyed_nodes_without_recreating_them() {
+        let backend = MemoryBackend::new();
+        let container = MemoryNode::container();
+        let mut old = keyed_list(&["a", "b", "c", "d", "e"]);
+
+        patch_dom(&backend, &container, VDom::new(), &mut old);
+
+        let before: Vec<MemoryNode> = ["a", "b", "c", "d", "e"]
+            .iter()
+            .map(|key| find_child(&container, key))
+            .collect();
+
+        patch_dom(
+            &backend,
+            &container,
+            old,
+            &mut keyed_list(&["e", "b", "a", "d", "c"]),
+        );
+
+        assert_eq!(
+            format!("{:?}", container),
+            "<li>e</li><li>b</li><li>a</li><li>d</li><li>c</li>"
+        );
+
+        for (key, node) in ["a", "b", "c", "d", "e"].iter().zip(before.iter()) {
+            assert!(find_child(&container, key).ptr_eq(node));
+        }
+    }
+
+    #[test]
+    fn reorders_keyed_nodes_with_insertions_and_removals() {
+        let backend = MemoryBackend::new();
+        let container = MemoryNode::container();
+        let mut old = keyed_list(&["a", "b", "c"]);
+
+        patch_dom(&backend, &container, VDom::new(), &mut old);
+
+        let a = find_child(&container, "a");
+        let c = find_child(&container, "c");
+
+        patch_dom(&backend, &container, old, &mut keyed_list(&["c", "x", "a"]));
+
+        assert_eq!(format!("{:?}", container), "<li>c</li><li>x</li><li>a</li>");
+        assert!(find_child(&container, "a").ptr_eq(&a));
+        assert!(find_child(&container, "c").ptr_eq(&c));
+    }
+
+    #[test]
+    fn inserts_and_removes_unkeyed_nodes() {
+        let backend = MemoryBackend::new();
+        let container = MemoryNode::container();
+        let mut old = VDom::new();
+
+        old.child(name!("p"), |mut p| p.text("a"));
+        old.child(name!("p"), |mut p| p.text("b"));
+
+        patch_dom(&backend, &container, VDom::new(), &mut old);
+
+        let first = container.children()[0].clone();
+        let mut new = VDom::new();
+
+        new.child(name!("p"), |mut p| p.text("a"));
+        new.child(name!("span"), |mut span| span.text("c"));
+        new.text("d");
+
+        patch_dom(&backend, &container, old, &mut new);
+
+        assert_eq!(format!("{:?}", container), "<p>a</p><span>c</span>d");
+        assert!(container.children()[0].ptr_eq(&first));
+
+        let mut newer = VDom::new();
+
+        newer.child(name!("p"), |mut p| p.text("a"));
+
+        patch_dom(&backend, &container, new, &mut newer);
+
+        assert_eq!(format!("{:?}", container), "<p>a</p>");
+        assert!(container.children()[0].ptr_eq(&first));
+    }
+
+    #[test]
+    fn sets_and_removes_namespaced_attributes() {
+        let backend = MemoryBackend::new();
+        let container = MemoryNode::container();
+        let mut old = VDom::new();
+
+        old.child_ns(Namespace::Svg, name!("use"), |mut element| {
+            element.attr_ns(Namespace::XLink, name!("xlink:href"), "#a");
+        });
+
+        patch_dom(&backend, &container, VDom::new(), &mut old);
+
+        let element = container.children()[0].clone();
+
+        assert_eq!(element.attribute("xlink:href").as_deref(), Some("#a"));
+        assert_eq!(
+            element.attribute_namespace("xlink:href"),
+            Some(Namespace::XLink)
+        );
+
+        let mut new = VDom::new();
+
+        new.child_ns(Namespace::Svg, name!("use"), |_| {});
+
+        patch_dom(&backend, &container, old, &mut new);
+
+        assert_eq!(element.attribute("xlink:href"), None);
+    }
+
+    fn memo_vdom(deps: u32, renders: &Rc<Cell<usize>>) -> VDom {
+        let renders = renders.clone();
+        let mut vdom = VDom::new();
+
+        vdom.memo(deps, move |deps, vdom| {
+            renders.set(renders.get() + 1);
+            vdom.child(name!("p"), |mut p| p.text(&deps.to_string()));
+        });
+
+        vdom
+    }
+
+    #[test]
+    fn skips_memos_with_equal_deps() {
+        let backend = MemoryBackend::new();
+        let container = MemoryNode::container();
+        let renders = Rc::new(Cell::new(0));
+        let mut old = memo_vdom(1, &renders);
+
+        patch_dom(&backend, &container, VDom::new(), &mut old);
+
+        let element = container.children()[0].clone();
+        let mut new = memo_vdom(1, &renders);
+
+        patch_dom(&backend, &container, old, &mut new);
+
+        assert_eq!(renders.get(), 1);
+        assert_eq!(format!("{:?}", container), "<p>1</p>");
+        assert!(container.children()[0].ptr_eq(&element));
+
+        patch_dom(&backend, &container, new, &mut memo_vdom(2, &renders));
+
+        assert_eq!(renders.get(), 2);
+        assert_eq!(format!("{:?}", container), "<p>2</p>");
+        assert!(container.children()[0].ptr_eq(&element));
+    }
+
+    fn fragment_vdom(texts: &[&str]) -> VDom {
+        let mut vdom = VDom::new();
+
+        vdom.child(name!("p"), |mut p| p.text("before"));
+        vdom.fragment(|mut fragment| {
+            for text in texts {
+                fragment.text(text);
+            }
+        });
+        vdom.child(name!("p"), |mut p| p.text("after"));
+
+        vdom
+    }
+
+    #[test]
+    fn patches_fragments_in_place() {
+        let backend = MemoryBackend::new();
+        let container = MemoryNode::container();
+        let mut old = fragment_vdom(&["x", "y"]);
+
+        patch_dom(&backend, &container, VDom::new(), &mut old);
+
+        assert_eq!(format!("{:?}", container), "<p>before</p>xy<p>after</p>");
+
+        let mut new = fragment_vdom(&["x", "y", "z"]);
+
+        patch_dom(&backend, &container, old, &mut new);
+
+        assert_eq!(format!("{:?}", container), "<p>before</p>xyz<p>after</p>");
+
+        patch_dom(&backend, &container, new, &mut fragment_vdom(&[]));
+
+        assert_eq!(format!("{:?}", container), "<p>before</p><p>after</p>");
+    }
+
+    struct Counter {
+        updater: Rc<RefCell<Option<Updater<u32>>>>,
+    }
+
+    impl Component for Counter {
+        type Props = &'static str;
+
+        type State = u32;
+
+        fn init(&self, _props: &Self::Props) -> Self::State {
+            0
+        }
+
+        fn render(
+            &self,
+            props: &Self::Props,
+            state: &Self::State,
+            updater: &Updater<Self::State>,
+            vdom: &mut VDom,
+        ) {
+            *self.updater.borrow_mut() = Some(updater.clone());
+
+            vdom.child(name!("p"), |mut p| p.text(&format!("{}: {}", props, state)));
+        }
+    }
+
+    fn counter_vdom(label: &'static str, updater: &Rc<RefCell<Option<Updater<u32>>>>) -> VDom {
+        let mut vdom = VDom::new();
+
+        vdom.component(
+            Counter {
+                updater: updater.clone(),
+            },
+            label,
+        );
+
+        vdom
+    }
+
+    #[test]
+    fn rerenders_updated_components() {
+        let backend = MemoryBackend::new();
+        let container = MemoryNode::container();
+        let updater = Rc::new(RefCell::new(None));
+        let mut vdom = counter_vdom("count", &updater);
+
+        patch_dom(&backend, &container, VDom::new(), &mut vdom);
+
+        let element = container.children()[0].clone();
+
+        assert_eq!(format!("{:?}", container), "<p>count: 0</p>");
+
+        let current = updater.borrow().clone().unwrap();
+
+        current.update(|count| *count += 1).unwrap();
+        patch_components(&backend, &container, &mut vdom);
+
+        assert_eq!(format!("{:?}", container), "<p>count: 1</p>");
+        assert!(container.children()[0].ptr_eq(&element));
+
+        // The state carries over into the component at the same position in the next vdom.
+        let mut new = counter_vdom("total", &updater);
+
+        patch_dom(&backend, &container, vdom, &mut new);
+
+        assert_eq!(format!("{:?}", container), "<p>total: 1</p>");
+        assert!(container.children()[0].ptr_eq(&element));
+    }
+
+    #[test]
+    fn adopted_components_report_updates_to_the_current_watcher() {
+        let backend = MemoryBackend::new();
+        let container = MemoryNode::container();
+        let updater = Rc::new(RefCell::new(None));
+        let first = ComponentWatcher::new();
+        let mut vdom = counter_vdom("count", &updater);
+
+        first.watch(|| patch_dom(&backend, &container, VDom::new(), &mut vdom));
+
+        // A new render loop (e.g. after the host element was connected again) adopts the
+        // component.
+        let second = ComponentWatcher::new();
+        let mut new = counter_vdom("count", &updater);
+
+        second.watch(|| patch_dom(&backend, &container, vdom, &mut new));
+
+        let current = updater.borrow().clone().unwrap();
+
+        current.update(|count| *count += 1).unwrap();
+
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        assert!(first.poll_updated(&mut cx).is_pending());
+        assert!(second.poll_updated(&mut cx).is_ready());
+    }
+}
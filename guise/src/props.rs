@@ -0,0 +1,140 @@
+use std::any::{type_name, Any};
+use std::cell::RefCell;
+use std::fmt;
+use std::marker;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+use futures::Stream;
+
+use crate::util::warn;
+
+/// Holds the typed props that a parent passed to an element (see `ElementBuilder::props`).
+///
+/// The slot is shared between the parent, which sets the props when it patches the element, and
+/// the element itself, which receives them through a [PropsChanged] stream.
+pub(crate) struct PropsSlot {
+    value: Option<Rc<dyn Any>>,
+    changed: bool,
+    disconnected: bool,
+    waker: Option<Waker>,
+}
+
+impl PropsSlot {
+    pub(crate) fn new() -> Self {
+        PropsSlot {
+            value: None,
+            changed: false,
+            disconnected: true,
+            waker: None,
+        }
+    }
+
+    pub(crate) fn set(&mut self, value: Rc<dyn Any>) {
+        self.value = Some(value);
+        self.changed = true;
+
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// Marks the element as connected; the current props (if any) are delivered to the new
+    /// [PropsChanged] stream.
+    pub(crate) fn connect(&mut self) {
+        self.disconnected = false;
+        self.changed = self.value.is_some();
+    }
+
+    /// Marks the element as disconnected, which ends the current [PropsChanged] stream.
+    pub(crate) fn disconnect(&mut self) {
+        self.disconnected = true;
+
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Describes props that a parent passed to an element, but that are not of the type the element's
+/// component expects (see [PropsChanged]).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PropsTypeError {
+    expected: &'static str,
+}
+
+impl PropsTypeError {
+    /// The name of the props type that the component expects.
+    pub fn expected(&self) -> &str {
+        self.expected
+    }
+}
+
+impl fmt::Display for PropsTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ignored props that are not of the expected type `{}`",
+            self.expected
+        )
+    }
+}
+
+pub(crate) fn warn_props_type_error(error: &PropsTypeError) {
+    warn(&error.to_string());
+}
+
+/// A stream of the typed props that the parent passes to an element.
+///
+/// Yields the current props when the element is connected (if the parent already set props), and
+/// then every time the parent sets props that do not compare equal to the previous props. Props
+/// of a type other than `P` are ignored and reported to
+/// [RegisterOptions::on_props_type_error](crate::RegisterOptions::on_props_type_error). Ends
+/// when the element is disconnected.
+pub struct PropsChanged<P> {
+    slot: Rc<RefCell<PropsSlot>>,
+    on_type_error: fn(&PropsTypeError),
+    _marker: marker::PhantomData<*const P>,
+}
+
+impl<P> PropsChanged<P> {
+    pub(crate) fn new(slot: Rc<RefCell<PropsSlot>>, on_type_error: fn(&PropsTypeError)) -> Self {
+        PropsChanged {
+            slot,
+            on_type_error,
+            _marker: Default::default(),
+        }
+    }
+}
+
+impl<P> Stream for PropsChanged<P>
+where
+    P: 'static,
+{
+    type Item = Rc<P>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut slot = self.slot.borrow_mut();
+
+        if slot.disconnected {
+            return Poll::Ready(None);
+        }
+
+        if slot.changed {
+            slot.changed = false;
+
+            match slot.value.clone().map(|value| value.downcast::<P>()) {
+                Some(Ok(props)) => return Poll::Ready(Some(props)),
+                Some(Err(_)) => (self.on_type_error)(&PropsTypeError {
+                    expected: type_name::<P>(),
+                }),
+                None => {}
+            }
+        }
+
+        slot.waker = Some(cx.waker().clone());
+
+        Poll::Pending
+    }
+}
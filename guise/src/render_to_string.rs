@@ -0,0 +1,410 @@
+use arwa::dom::{ShadowRootMode, ShadowRootOptions};
+
+use crate::scoped_style::ScopedStyle;
+use crate::vdom::{Element, Namespace, Node, VDom};
+
+/// Elements that cannot have any content; these are serialized without an end tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "basefont", "bgsound", "br", "col", "embed", "frame", "hr", "img", "input",
+    "keygen", "link", "meta", "param", "source", "track", "wbr",
+];
+
+/// Elements whose text content is not parsed as markup; text inside these elements is serialized
+/// without escaping, except for sequences that would end the element early (see
+/// [write_raw_text]).
+const RAW_TEXT_ELEMENTS: &[&str] = &[
+    "iframe",
+    "noembed",
+    "noframes",
+    "noscript",
+    "plaintext",
+    "script",
+    "style",
+    "xmp",
+];
+
+/// Serializes a virtual DOM to an HTML string.
+///
+/// Follows the HTML fragment serialization algorithm: text and attribute values are escaped,
+/// void elements (e.g. `<br>`, `<input>`) are emitted without an end tag and the text content of
+/// raw text elements (e.g. `<style>`, `<script>`) is emitted as is. Customized built-in elements
+/// are emitted with an `is` attribute, and elements in the scope of a
+/// [ScopedStyle](crate::ScopedStyle) with its scoping attribute (the style's CSS itself is not
+/// part of the output, see [ScopedStyle::css](crate::ScopedStyle::css)). Adjacent text nodes are
+/// separated by an empty comment, so that the output can be hydrated (see
+/// [RegisterOptions::hydrate](crate::RegisterOptions)).
+///
+/// In the text content of a raw text element, any `</` that is followed by the element's tag
+/// name (e.g. `</script` inside a `<script>` element) is emitted as `<\/`, so that the content
+/// cannot end the element early.
+///
+/// DOM properties, event sinks, element refs and `on_rendered` callbacks are not represented in
+/// the output.
+pub fn render_to_string(vdom: &VDom) -> String {
+    let mut output = String::new();
+
+    vdom.with_nodes(|nodes| {
+        write_nodes(&mut output, nodes, false, &mut false, vdom.scoped_style());
+    });
+
+    output
+}
+
+/// Serializes a virtual DOM to an HTML string, wrapped in a declarative shadow root.
+///
+/// The output is a `<template shadowrootmode="...">` element that contains the serialization of
+/// the `vdom` (see [render_to_string]); when placed as the first child of a host element, the
+/// HTML parser attaches it as the host's shadow root. Pass the same `shadow_root_options` that
+/// are used to [register](crate::register_with_shadow_root) the component, so that a hydrating
+/// component can adopt the shadow root (see [RegisterOptions::hydrate](crate::RegisterOptions)).
+pub fn render_to_string_with_shadow_root(
+    vdom: &VDom,
+    shadow_root_options: ShadowRootOptions,
+) -> String {
+    let mode = match shadow_root_options.mode {
+        ShadowRootMode::Open => "open",
+        ShadowRootMode::Closed => "closed",
+    };
+
+    let mut output = String::new();
+
+    output.push_str("<template shadowrootmode=\"");
+    output.push_str(mode);
+    output.push('"');
+
+    if shadow_root_options.delegates_focus {
+        output.push_str(" shadowrootdelegatesfocus=\"\"");
+    }
+
+    output.push('>');
+
+    vdom.with_nodes(|nodes| {
+        write_nodes(&mut output, nodes, false, &mut false, vdom.scoped_style());
+    });
+
+    output.push_str("</template>");
+
+    output
+}
+
+fn write_nodes(
+    output: &mut String,
+    nodes: &[Node],
+    raw_text: bool,
+    previous_was_text: &mut bool,
+    scope: Option<ScopedStyle>,
+) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => {
+                if raw_text {
+                    output.push_str(text);
+                } else {
+                    // Adjacent text nodes would be parsed back as a single text node; separate
+                    // them with an empty comment so that hydration finds the same node structure.
+                    if *previous_was_text {
+                        output.push_str("<!---->");
+                    }
+
+                    write_escaped(output, text, false);
+                }
+
+                *previous_was_text = true;
+            }
+            Node::Element(element) => {
+                write_element(output, element, scope);
+
+                *previous_was_text = false;
+            }
+            Node::Fragment(fragment) => {
+                write_nodes(
+                    output,
+                    fragment.children(),
+                    raw_text,
+                    previous_was_text,
+                    scope,
+                );
+            }
+            Node::Memo(memo) => {
+                // The nodes of a memo that has not been rendered yet are rendered into a temporary
+                // virtual DOM.
+                let rendered;
+                let vdom = if let Some(vdom) = memo.rendered() {
+                    vdom
+                } else {
+                    rendered = memo.render();
+
+                    &rendered
+                };
+
+                let scope = vdom.scoped_style().or(scope);
+
+                vdom.with_nodes(|nodes| {
+                    write_nodes(output, nodes, raw_text, previous_was_text, scope)
+                });
+            }
+            Node::Component(component) => {
+                // Components that have not been rendered yet are rendered into a temporary
+                // virtual DOM, with freshly initialized state.
+                let instance = component.instance();
+                let rendered;
+                let vdom = if let Some(vdom) = instance.rendered() {
+                    vdom
+                } else {
+                    rendered = instance.render();
+
+                    &rendered
+                };
+
+                let scope = vdom.scoped_style().or(scope);
+
+                vdom.with_nodes(|nodes| {
+                    write_nodes(output, nodes, raw_text, previous_was_text, scope)
+                });
+            }
+        }
+    }
+}
+
+fn write_element(output: &mut String, element: &Element, scope: Option<ScopedStyle>) {
+    let tag_name: &str = element.tag_name().as_ref();
+
+    output.push('<');
+    output.push_str(tag_name);
+
+    if let Some(is) = element.is() {
+        let is: &str = is.as_ref();
+
+        output.push_str(" is=\"");
+        write_escaped(output, is, true);
+        output.push('"');
+    }
+
+    for attribute in element.attributes() {
+        let name: &str = attribute.name().as_ref();
+
+        output.push(' ');
+        output.push_str(name);
+        output.push_str("=\"");
+        write_escaped(output, attribute.value(), true);
+        output.push('"');
+    }
+
+    if let Some(scope) = scope {
+        output.push(' ');
+        output.push_str(scope.attribute());
+        output.push_str("=\"\"");
+    }
+
+    output.push('>');
+
+    // Only elements in the HTML namespace can be void elements or raw text elements; e.g. an SVG
+    // `<style>` element is not.
+    let is_html = element.namespace() == Namespace::Html;
+
+    if is_html && is_one_of(tag_name, VOID_ELEMENTS) {
+        return;
+    }
+
+    if is_html && is_one_of(tag_name, RAW_TEXT_ELEMENTS) {
+        // The content is collected first, as an end tag may be split across text nodes.
+        let mut content = String::new();
+
+        write_nodes(&mut content, element.children(), true, &mut false, scope);
+        write_raw_text(output, &content, tag_name);
+    } else {
+        write_nodes(output, element.children(), false, &mut false, scope);
+    }
+
+    output.push_str("</");
+    output.push_str(tag_name);
+    output.push('>');
+}
+
+fn write_escaped(output: &mut String, text: &str, attribute_mode: bool) {
+    for c in text.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '\u{a0}' => output.push_str("&nbsp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' if attribute_mode => output.push_str("&quot;"),
+            c => output.push(c),
+        }
+    }
+}
+
+/// Writes the `text` content of the raw text element with the given `tag_name`, replacing every
+/// `</` that is followed by the tag name (compared case-insensitively) with `<\/`.
+///
+/// The HTML parser ends a raw text element at the first such sequence; with the replacement the
+/// content is parsed as text instead. Inside `<script>` and `<style>` elements, `<\/` is an
+/// escaped `</` in JavaScript strings and CSS respectively.
+fn write_raw_text(output: &mut String, text: &str, tag_name: &str) {
+    let mut rest = text;
+
+    while let Some(index) = rest.find("</") {
+        let after = &rest[index + 2..];
+        let is_end_tag = after
+            .get(..tag_name.len())
+            .map(|name| name.eq_ignore_ascii_case(tag_name))
+            .unwrap_or(false);
+
+        output.push_str(&rest[..index]);
+        output.push_str(if is_end_tag { "<\\/" } else { "</" });
+
+        rest = after;
+    }
+
+    output.push_str(rest);
+}
+
+fn is_one_of(tag_name: &str, names: &[&str]) -> bool {
+    names.iter().any(|n| unicase::eq_ascii(tag_name, n))
+}
+
+#[cfg(test)]
+mod tests {
+    use arwa::dom::name;
+    use arwa::html::custom_element_name;
+
+    use super::*;
+
+    fn element_with_text(tag_name: arwa::dom::Name, texts: &[&str]) -> VDom {
+        let mut vdom = VDom::new();
+
+        vdom.child(tag_name, |mut element| {
+            for text in texts {
+                element.text(text);
+            }
+        });
+
+        vdom
+    }
+
+    #[test]
+    fn escapes_text_and_attributes() {
+        let mut vdom = VDom::new();
+
+        vdom.child(name!("div"), |mut div| {
+            div.attr(name!("title"), "a \"quoted\" <value> & more");
+            div.text("1 < 2 & 3 > 2");
+        });
+
+        insta::assert_snapshot!(
+            render_to_string(&vdom),
+            @r###"<div title="a &quot;quoted&quot; &lt;value&gt; &amp; more">1 &lt; 2 &amp; 3 &gt; 2</div>"###
+        );
+    }
+
+    #[test]
+    fn void_elements_have_no_end_tag() {
+        let mut vdom = VDom::new();
+
+        vdom.child(name!("p"), |mut p| {
+            p.child(name!("br"), |_| {});
+            p.child(name!("input"), |mut input| {
+                input.boolean_attr(name!("disabled"))
+            });
+        });
+
+        insta::assert_snapshot!(render_to_string(&vdom), @r###"<p><br><input disabled=""></p>"###);
+    }
+
+    #[test]
+    fn customized_built_in_elements_have_an_is_attribute() {
+        let mut vdom = VDom::new();
+
+        vdom.child_customized(
+            name!("button"),
+            custom_element_name!("x-button"),
+            |mut button| {
+                button.attr(name!("class"), "primary");
+                button.text("Save");
+            },
+        );
+
+        insta::assert_snapshot!(
+            render_to_string(&vdom),
+            @r###"<button is="x-button" class="primary">Save</button>"###
+        );
+    }
+
+    #[test]
+    fn namespaced_attributes_use_their_qualified_name() {
+        let mut vdom = VDom::new();
+
+        vdom.child(name!("svg"), |mut svg| {
+            svg.child(name!("use"), |mut use_element| {
+                use_element.attr_ns(Namespace::XLink, name!("xlink:href"), "#icon");
+                use_element.attr_ns(Namespace::Xml, name!("xml:lang"), "en");
+            });
+        });
+
+        insta::assert_snapshot!(
+            render_to_string(&vdom),
+            @r###"<svg><use xlink:href="#icon" xml:lang="en"></use></svg>"###
+        );
+    }
+
+    #[test]
+    fn separates_adjacent_text_nodes() {
+        let vdom = element_with_text(name!("span"), &["a", "b", "c"]);
+
+        insta::assert_snapshot!(render_to_string(&vdom), @"<span>a<!---->b<!---->c</span>");
+    }
+
+    #[test]
+    fn raw_text_is_not_escaped() {
+        let vdom = element_with_text(name!("style"), &["a > b { content: \"&\"; }"]);
+
+        insta::assert_snapshot!(
+            render_to_string(&vdom),
+            @r###"<style>a > b { content: "&"; }</style>"###
+        );
+    }
+
+    #[test]
+    fn raw_text_cannot_end_its_element() {
+        let vdom = element_with_text(
+            name!("script"),
+            &["let a = \"</script><img src=x onerror=alert(1)>\"; let b = \"</SCRIPT \";"],
+        );
+
+        insta::assert_snapshot!(
+            render_to_string(&vdom),
+            @r###"<script>let a = "<\/script><img src=x onerror=alert(1)>"; let b = "<\/SCRIPT ";</script>"###
+        );
+    }
+
+    #[test]
+    fn raw_text_end_tag_split_across_text_nodes() {
+        let vdom = element_with_text(name!("style"), &["</st", "yle>"]);
+
+        insta::assert_snapshot!(render_to_string(&vdom), @r###"<style><\/style></style>"###);
+    }
+
+    #[test]
+    fn raw_text_keeps_other_end_tags() {
+        let vdom = element_with_text(name!("script"), &["document.write(\"</p>\")"]);
+
+        insta::assert_snapshot!(
+            render_to_string(&vdom),
+            @r###"<script>document.write("</p>")</script>"###
+        );
+    }
+
+    #[test]
+    fn svg_style_is_not_raw_text() {
+        let mut vdom = VDom::new();
+
+        vdom.child(name!("svg"), |mut svg| {
+            svg.child(name!("style"), |mut style| style.text("a > b"));
+        });
+
+        insta::assert_snapshot!(
+            render_to_string(&vdom),
+            @"<svg><style>a &gt; b</style></svg>"
+        );
+    }
+}
@@ -0,0 +1,502 @@
+use arwa::dom::{Name, StaticallyParsedName};
+
+/// CSS that only applies to the elements rendered by the [VDom](crate::VDom)s that use it (see
+/// `VDom::set_scoped_style`), for components that render into the light DOM.
+///
+/// Every selector in the CSS is rewritten to also require a scoping attribute (e.g.
+/// `.item:hover::before` becomes `.item:hover[data-guise-5d9b0f31]::before`), and every element
+/// created for a vdom that uses the style is stamped with that attribute. Selectors that target
+/// the document's root element or a shadow host (e.g. `:root` or `:host(.dark)`) are left
+/// unscoped, as those elements are not rendered by a vdom.
+///
+/// The name of the attribute is derived from a hash of the CSS, so that it is the same on a
+/// server that pre-renders markup and in the browser that hydrates it. The CSS is added to a
+/// document (as a `<style>` element in its `<head>`) or to a shadow root the first time a vdom
+/// that uses it is rendered there.
+///
+/// A scoped style is meant to be created once per component (e.g. in a `thread_local!`); its CSS
+/// is never freed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ScopedStyle {
+    attribute: &'static str,
+    css: &'static str,
+}
+
+impl ScopedStyle {
+    pub fn new(css: &str) -> Self {
+        let attribute = format!("data-guise-{:08x}", fnv1a(css));
+        let css = scope_css(css, &attribute);
+
+        ScopedStyle {
+            attribute: Box::leak(attribute.into_boxed_str()),
+            css: Box::leak(css.into_boxed_str()),
+        }
+    }
+
+    /// The name of the scoping attribute.
+    pub fn attribute(&self) -> &'static str {
+        self.attribute
+    }
+
+    /// The rewritten CSS, e.g. to include it in pre-rendered markup.
+    pub fn css(&self) -> &'static str {
+        self.css
+    }
+
+    pub(crate) fn attribute_name(&self) -> Name {
+        Name::from_statically_parsed(StaticallyParsedName {
+            name: self.attribute,
+        })
+    }
+}
+
+/// Hashes the `css` with 32-bit FNV-1a, which (unlike the standard library's hashers) is stable
+/// across platforms and compiler versions.
+fn fnv1a(css: &str) -> u32 {
+    css.bytes().fold(0x811c9dc5, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    })
+}
+
+/// At-rules whose blocks contain style rules that should be scoped.
+const GROUPING_AT_RULES: &[&str] = &["media", "supports", "layer", "container", "document"];
+
+/// Pseudo-elements that may be written with a single colon.
+const LEGACY_PSEUDO_ELEMENTS: &[&str] = &["before", "after", "first-line", "first-letter"];
+
+/// Pseudo-classes that match the document's root element or a shadow host, which are not rendered
+/// by a vdom and so never carry the scoping attribute.
+const UNSCOPED_PSEUDO_CLASSES: &[&str] = &["root", "host", "host-context"];
+
+/// Rewrites every selector in the `css` so that it only matches elements with the `attribute`.
+fn scope_css(css: &str, attribute: &str) -> String {
+    let css = strip_comments(css);
+    let mut output = String::with_capacity(css.len());
+
+    scope_rules(&css, attribute, &mut output);
+
+    output
+}
+
+fn scope_rules(css: &str, attribute: &str, output: &mut String) {
+    let mut rest = css;
+
+    while !rest.trim().is_empty() {
+        let prelude_end = find_top_level(rest, |c| c == '{' || c == ';').unwrap_or(rest.len());
+        let prelude = &rest[..prelude_end];
+
+        if rest[prelude_end..].starts_with('{') {
+            let block_end = matching_brace(rest, prelude_end);
+            let block = &rest[prelude_end + 1..block_end];
+            let trimmed = prelude.trim();
+
+            if let Some(at_rule) = trimmed.strip_prefix('@') {
+                let name: String = at_rule
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || *c == '-')
+                    .collect::<String>()
+                    .to_ascii_lowercase();
+
+                output.push_str(trimmed);
+                output.push('{');
+
+                if GROUPING_AT_RULES.contains(&name.as_str()) {
+                    scope_rules(block, attribute, output);
+                } else {
+                    // E.g. `@keyframes` or `@font-face`, which don't contain selectors.
+                    output.push_str(block);
+                }
+
+                output.push('}');
+            } else {
+                output.push_str(&scope_selector_list(trimmed, attribute));
+                output.push('{');
+                output.push_str(block);
+                output.push('}');
+            }
+
+            rest = &rest[(block_end + 1).min(rest.len())..];
+        } else {
+            // A statement at-rule (e.g. `@import`), or trailing garbage.
+            output.push_str(prelude.trim());
+            output.push(';');
+
+            rest = &rest[(prelude_end + 1).min(rest.len())..];
+        }
+    }
+}
+
+fn scope_selector_list(selectors: &str, attribute: &str) -> String {
+    let mut scoped = Vec::new();
+    let mut rest = selectors;
+
+    loop {
+        let end = find_top_level(rest, |c| c == ',').unwrap_or(rest.len());
+
+        scoped.push(scope_selector(rest[..end].trim(), attribute));
+
+        if end == rest.len() {
+            break;
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    scoped.join(",")
+}
+
+/// Adds the `attribute` to the last compound selector of the complex `selector`, before its
+/// pseudo-element (if any).
+///
+/// A selector whose last compound selector starts with `:root`, `:host` or `:host-context` is
+/// left as is.
+fn scope_selector(selector: &str, attribute: &str) -> String {
+    let scope = format!("[{}]", attribute);
+    let bytes = selector.as_bytes();
+
+    // The start of the last compound selector: the position after the last combinator.
+    let mut compound_start = 0;
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+
+    for (i, c) in selector.char_indices() {
+        if escaped {
+            escaped = false;
+
+            continue;
+        }
+
+        if c == '\\' {
+            escaped = true;
+
+            continue;
+        }
+
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '(' | '[' => depth += 1,
+                ')' | ']' => depth -= 1,
+                ' ' | '\t' | '\n' | '>' | '+' | '~' if depth == 0 => compound_start = i + 1,
+                _ => {}
+            },
+        }
+    }
+
+    let compound = &selector[compound_start..];
+
+    if is_unscoped(compound) {
+        return selector.to_string();
+    }
+
+    let mut insert_at = selector.len();
+    let mut depth = 0;
+    let mut escaped = false;
+
+    for (i, c) in compound.char_indices() {
+        if escaped {
+            escaped = false;
+
+            continue;
+        }
+
+        match c {
+            '\\' => escaped = true,
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ':' if depth == 0 => {
+                let position = compound_start + i;
+                let after = &compound[i + 1..];
+                let is_pseudo_element = after.starts_with(':')
+                    || LEGACY_PSEUDO_ELEMENTS.iter().any(|name| {
+                        after
+                            .get(..name.len())
+                            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(name))
+                            && !after[name.len()..]
+                                .starts_with(|c: char| c.is_alphanumeric() || c == '-')
+                    });
+
+                if is_pseudo_element && (position == 0 || bytes[position - 1] != b':') {
+                    insert_at = position;
+
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut scoped = String::with_capacity(selector.len() + scope.len());
+
+    scoped.push_str(&selector[..insert_at]);
+    scoped.push_str(&scope);
+    scoped.push_str(&selector[insert_at..]);
+
+    scoped
+}
+
+/// Returns `true` if the `compound` selector starts with one of the [UNSCOPED_PSEUDO_CLASSES].
+fn is_unscoped(compound: &str) -> bool {
+    if let Some(pseudo_class) = compound.strip_prefix(':') {
+        let name_end = pseudo_class
+            .find(|c: char| !(c.is_alphanumeric() || c == '-'))
+            .unwrap_or(pseudo_class.len());
+
+        UNSCOPED_PSEUDO_CLASSES
+            .iter()
+            .any(|name| pseudo_class[..name_end].eq_ignore_ascii_case(name))
+    } else {
+        false
+    }
+}
+
+/// Returns the position of the first character that matches the `predicate` outside of strings,
+/// parentheses and brackets; escaped characters never match.
+fn find_top_level<F>(css: &str, predicate: F) -> Option<usize>
+where
+    F: Fn(char) -> bool,
+{
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+
+    for (i, c) in css.char_indices() {
+        if escaped {
+            escaped = false;
+
+            continue;
+        }
+
+        if c == '\\' {
+            escaped = true;
+
+            continue;
+        }
+
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '(' | '[' => depth += 1,
+                ')' | ']' => depth -= 1,
+                _ if depth == 0 && predicate(c) => return Some(i),
+                _ => {}
+            },
+        }
+    }
+
+    None
+}
+
+/// Returns the position of the `}` that closes the `{` at `open`, or the end of the `css` if the
+/// block is not closed.
+fn matching_brace(css: &str, open: usize) -> usize {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+
+    for (i, c) in css[open..].char_indices() {
+        if escaped {
+            escaped = false;
+
+            continue;
+        }
+
+        if c == '\\' {
+            escaped = true;
+
+            continue;
+        }
+
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+
+                    if depth == 0 {
+                        return open + i;
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+
+    css.len()
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut output = String::with_capacity(css.len());
+    let mut rest = css;
+
+    while let Some(start) = find_top_level_comment(rest) {
+        output.push_str(&rest[..start]);
+
+        rest = match rest[start + 2..].find("*/") {
+            Some(end) => &rest[start + 2 + end + 2..],
+            None => "",
+        };
+    }
+
+    output.push_str(rest);
+
+    output
+}
+
+fn find_top_level_comment(css: &str) -> Option<usize> {
+    let mut quote = None;
+    let bytes = css.as_bytes();
+
+    for (i, c) in css.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '/' if bytes.get(i + 1) == Some(&b'*') => return Some(i),
+                _ => {}
+            },
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(css: &str) -> String {
+        scope_css(css, "x")
+    }
+
+    #[test]
+    fn scopes_selector_lists() {
+        assert_eq!(
+            scope("h1, .title > em { color: red }"),
+            "h1[x],.title > em[x]{ color: red }"
+        );
+    }
+
+    #[test]
+    fn scopes_the_last_compound_selector() {
+        assert_eq!(scope("ul>li+li~li{}"), "ul>li+li~li[x]{}");
+        assert_eq!(scope("nav\na.active{}"), "nav\na.active[x]{}");
+    }
+
+    #[test]
+    fn scopes_before_pseudo_elements() {
+        assert_eq!(scope(".a::before{}"), ".a[x]::before{}");
+        assert_eq!(scope(".a:hover:before{}"), ".a:hover[x]:before{}");
+        assert_eq!(scope(".a:BEFORE{}"), ".a[x]:BEFORE{}");
+        assert_eq!(scope(".a:after-all{}"), ".a:after-all[x]{}");
+    }
+
+    #[test]
+    fn does_not_split_functional_pseudo_classes() {
+        assert_eq!(scope("p:not(.a, .b){}"), "p:not(.a, .b)[x]{}");
+        assert_eq!(scope(":is(h1, h2) em{}"), ":is(h1, h2) em[x]{}");
+    }
+
+    #[test]
+    fn does_not_split_attribute_selectors() {
+        assert_eq!(
+            scope(r#"a[href="https://a.b/{c},d"]{}"#),
+            r#"a[href="https://a.b/{c},d"][x]{}"#
+        );
+        assert_eq!(
+            scope("a[title='a b']::after{}"),
+            "a[title='a b'][x]::after{}"
+        );
+    }
+
+    #[test]
+    fn scopes_nested_grouping_rules() {
+        assert_eq!(
+            scope("@media (min-width: 10px) { @supports (display: grid) { .a { b: c } } }"),
+            "@media (min-width: 10px){@supports (display: grid){.a[x]{ b: c }}}"
+        );
+    }
+
+    #[test]
+    fn leaves_other_at_rules_untouched() {
+        assert_eq!(
+            scope("@keyframes spin { from { a: b } to { c: d } }"),
+            "@keyframes spin{ from { a: b } to { c: d } }"
+        );
+        assert_eq!(
+            scope("@font-face { font-family: x; src: url(x.woff) }"),
+            "@font-face{ font-family: x; src: url(x.woff) }"
+        );
+        assert_eq!(
+            scope("@import url(a.css); p{}"),
+            "@import url(a.css);p[x]{}"
+        );
+    }
+
+    #[test]
+    fn strips_comments() {
+        assert_eq!(
+            scope("/* a { } */ p /* b */ { color: red /* } */ }"),
+            "p[x]{ color: red  }"
+        );
+        assert_eq!(
+            scope(r#"p::before { content: "/* not a comment */" }"#),
+            r#"p[x]::before{ content: "/* not a comment */" }"#
+        );
+    }
+
+    #[test]
+    fn ignores_braces_in_strings() {
+        assert_eq!(
+            scope(r#"p::before { content: "}" } q{}"#),
+            r#"p[x]::before{ content: "}" }q[x]{}"#
+        );
+        assert_eq!(
+            scope(r#"p::before { content: "\"}" } q{}"#),
+            r#"p[x]::before{ content: "\"}" }q[x]{}"#
+        );
+    }
+
+    #[test]
+    fn respects_escaped_characters() {
+        assert_eq!(scope(r".a\:hover{}"), r".a\:hover[x]{}");
+        assert_eq!(scope(r".w-1\/2\,b:hover{}"), r".w-1\/2\,b:hover[x]{}");
+        assert_eq!(scope(r".a\:before::after{}"), r".a\:before[x]::after{}");
+    }
+
+    #[test]
+    fn leaves_root_and_host_selectors_unscoped() {
+        assert_eq!(
+            scope(":root { --a: b } :host(.dark), :HOST-context(main) {}"),
+            ":root{ --a: b }:host(.dark),:HOST-context(main){}"
+        );
+        assert_eq!(
+            scope(":root.dark .a, :hostile{}"),
+            ":root.dark .a[x],:hostile[x]{}"
+        );
+    }
+
+    #[test]
+    fn derives_the_attribute_from_the_css() {
+        let style = ScopedStyle::new("p { color: red }");
+
+        assert_eq!(
+            style.attribute(),
+            format!("data-guise-{:08x}", fnv1a("p { color: red }"))
+        );
+        assert_eq!(style, ScopedStyle::new("p { color: red }"));
+        assert_ne!(
+            style.attribute(),
+            ScopedStyle::new("p { color: blue }").attribute()
+        );
+    }
+}
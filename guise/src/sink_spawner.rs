@@ -5,13 +5,14 @@ use std::pin::Pin;
 use std::task::{Context, Poll};
 
 use arwa::dom::DynamicElement;
-use arwa::event::{EventTarget, OnEvent, TypedEvent};
+use arwa::event::{EventOptions, EventTarget, OnEvent, TypedEvent};
 use arwa::spawn_local;
 use futures::future::AbortHandle;
 use futures::ready;
 use futures::stream::Abortable;
 use futures::{Sink, Stream};
 
+use crate::listener::ListenerOptions;
 use crate::raw_sink::RawSink;
 
 enum State {
@@ -22,11 +23,12 @@ enum State {
 
 pub(crate) struct SinkSpawner {
     state: State,
-    spawner: fn(&DynamicElement, RawSink) -> AbortHandle,
+    options: ListenerOptions,
+    spawner: fn(&DynamicElement, RawSink, ListenerOptions) -> AbortHandle,
 }
 
 impl SinkSpawner {
-    pub(crate) fn new<E, T, S>(sink: S) -> Self
+    pub(crate) fn new<E, T, S>(sink: S, options: ListenerOptions) -> Self
     where
         E: EventTarget,
         T: TypedEvent<CurrentTarget = E> + 'static,
@@ -35,15 +37,20 @@ impl SinkSpawner {
     {
         SinkSpawner {
             state: State::Unused(RawSink::new(sink)),
+            options,
             spawner: spawn::<E, T>,
         }
     }
 
     pub(crate) fn spawn(&mut self, target: &DynamicElement) {
-        let SinkSpawner { state, spawner } = self;
+        let SinkSpawner {
+            state,
+            options,
+            spawner,
+        } = self;
 
         if let State::Unused(sink) = mem::replace(state, State::Gone) {
-            *state = State::Spawned(spawner(target, sink));
+            *state = State::Spawned(spawner(target, sink, *options));
         } else {
             panic!("already spawned")
         }
@@ -61,13 +68,18 @@ impl Drop for SinkSpawner {
 fn spawn<E: EventTarget, T: TypedEvent<CurrentTarget = E> + 'static>(
     target: &DynamicElement,
     sink: RawSink,
+    options: ListenerOptions,
 ) -> AbortHandle {
     // Safety: the actual concrete type of the DynamicElement does not matter to Arwa, it is only
     // used to cast the `current_target` on events in the stream, which never leads to unsafety (at
     // worst incorrectness). The concrete type of the DynamicElement should also always match `E`,
     // this is internal to Guise.
     let target: &E = unsafe { mem::transmute(target) };
-    let stream = target.on_typed_event::<T>();
+    let stream = target.on_typed_event_with_options::<T>(EventOptions {
+        capture: options.capture,
+        passive: options.passive,
+        once: options.once,
+    });
     let (abort_handle, registration) = AbortHandle::new_pair();
 
     let stream = Abortable::new(stream, registration);
@@ -76,6 +88,8 @@ fn spawn<E: EventTarget, T: TypedEvent<CurrentTarget = E> + 'static>(
         stream,
         raw_sink: sink,
         buffered: None,
+        once: options.once,
+        ended: false,
     });
 
     abort_handle
@@ -85,6 +99,10 @@ struct SinkTask<T> {
     stream: Abortable<OnEvent<T>>,
     raw_sink: RawSink,
     buffered: Option<*mut ()>,
+    once: bool,
+    /// Set after the first event if `once` is set, as the browser removes the listener after that
+    /// event; the task completes once the event is flushed, which drops the sink.
+    ended: bool,
 }
 
 impl<T> SinkTask<T> {
@@ -122,10 +140,18 @@ where
         }
 
         loop {
+            if this.ended {
+                ready!(this.raw_sink.poll_flush(cx));
+
+                return Poll::Ready(());
+            }
+
             let pinned = unsafe { Pin::new_unchecked(&mut this.stream) };
 
             match pinned.poll_next(cx) {
                 Poll::Ready(Some(event)) => {
+                    this.ended = this.once;
+
                     let ptr = Box::into_raw(Box::new(event)) as *mut ();
 
                     ready!(this.start_send(cx, ptr))
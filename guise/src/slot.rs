@@ -0,0 +1,313 @@
+use std::cell::RefCell;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+use arwa::collection::Sequence;
+use arwa::dom::{name, CharacterData, DynamicChildNode, DynamicElement, Element, ParentNode, Text};
+use futures::Stream;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+// Arwa does not support slot assignment or mutation observers, so we bind these directly.
+//
+// A rendered slot reports changes to its assignment with `slotchange` events, which bubble to the
+// shadow root; listening on the shadow root also covers a slot that is rendered later. While the
+// slot is not rendered, its assignment is derived from the host's children, so we also observe
+// the host's child list and the `slot` attributes of its children. Note that the attribute
+// observation has to cover the host's subtree, as attribute changes of children are only reported
+// for the subtree; changes to deeper descendants are filtered out by comparing the assignment.
+#[wasm_bindgen(inline_js = r#"
+export function find_slot(shadow_root, name) {
+    for (const slot of shadow_root.querySelectorAll("slot")) {
+        if ((slot.getAttribute("name") || "") === name) {
+            return slot;
+        }
+    }
+
+    return null;
+}
+
+// Like `assignedNodes({ flatten: true })`, but without fallback content: a slot that nothing is
+// assigned to contributes no nodes, rather than its own children.
+export function assigned_nodes(slot) {
+    const nodes = [];
+
+    for (const node of slot.assignedNodes()) {
+        if (node.localName === "slot" && typeof node.assignedNodes === "function") {
+            nodes.push(...assigned_nodes(node));
+        } else {
+            nodes.push(node);
+        }
+    }
+
+    return nodes;
+}
+
+export function observe_assignment(host, shadow_root, callback) {
+    const children = new MutationObserver(callback);
+    const slot_attributes = new MutationObserver(callback);
+
+    children.observe(host, { childList: true });
+    slot_attributes.observe(host, {
+        subtree: true,
+        attributes: true,
+        attributeFilter: ["slot"],
+    });
+
+    if (shadow_root) {
+        shadow_root.addEventListener("slotchange", callback);
+    }
+
+    return () => {
+        children.disconnect();
+        slot_attributes.disconnect();
+
+        if (shadow_root) {
+            shadow_root.removeEventListener("slotchange", callback);
+        }
+    };
+}
+"#)]
+extern "C" {
+    fn find_slot(shadow_root: &JsValue, name: &str) -> JsValue;
+
+    fn assigned_nodes(slot: &JsValue) -> Box<[JsValue]>;
+
+    fn observe_assignment(host: &JsValue, shadow_root: &JsValue, callback: &JsValue) -> Function;
+}
+
+#[wasm_bindgen]
+extern "C" {
+    type Function;
+
+    #[wasm_bindgen(method, js_name = call)]
+    fn call0(this: &Function, context: &JsValue);
+}
+
+/// A slot in the shadow root of a component's host element (see [Host::slot]).
+///
+/// While the slot is rendered into the shadow root, its assignment is the browser's flattened
+/// assignment, so nodes that are assigned to a slot that the host is itself assigned to are
+/// included. Unlike `assignedNodes({ flatten: true })`, the assignment never includes the
+/// fallback content of a slot (the slot's own children), so a rendered slot with fallback content
+/// has no content while nothing is assigned to it.
+///
+/// While the slot is not rendered, its assignment is derived from the host element's children,
+/// following the browser's slot assignment: elements are assigned to the slot named by their
+/// `slot` attribute, text nodes and elements without a `slot` attribute are assigned to the
+/// default slot. This is the assignment the slot would have if it were rendered, so a component
+/// can decide whether to render a slot (or the area around it) based on its assignment.
+///
+/// [Host::slot]: crate::Host::slot
+#[derive(Clone)]
+pub struct Slot {
+    host: JsValue,
+    shadow_root: Option<JsValue>,
+    name: String,
+}
+
+impl Slot {
+    pub(crate) fn new(host: JsValue, shadow_root: Option<JsValue>, name: &str) -> Self {
+        Slot {
+            host,
+            shadow_root,
+            name: name.to_string(),
+        }
+    }
+
+    /// The name of the slot; the empty string for the default slot.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the current assignment of the slot.
+    pub fn assignment(&self) -> SlotAssignment {
+        let rendered = self
+            .shadow_root
+            .as_ref()
+            .map(|shadow_root| find_slot(shadow_root, &self.name))
+            .filter(|slot| !slot.is_null());
+
+        if let Some(slot) = rendered {
+            let mut nodes = Vec::new();
+            let mut has_content = false;
+
+            for node in assigned_nodes(&slot).iter() {
+                let node: DynamicChildNode = node.clone().unchecked_into();
+
+                match DynamicElement::try_from(node.clone()) {
+                    Ok(_) => has_content = true,
+                    Err(node) => {
+                        if let Ok(text) = Text::try_from(node) {
+                            has_content |= !text.data().trim().is_empty();
+                        }
+                    }
+                }
+
+                nodes.push(node);
+            }
+
+            return SlotAssignment { nodes, has_content };
+        }
+
+        let host: &DynamicElement = self.host.unchecked_ref();
+        let children = host.child_nodes();
+        let mut nodes = Vec::new();
+        let mut has_content = false;
+
+        for node in (0..children.len()).filter_map(|i| children.get(i)) {
+            match DynamicElement::try_from(node) {
+                Ok(element) => {
+                    let slot = element
+                        .attributes()
+                        .lookup(&name!("slot"))
+                        .map(|attribute| attribute.value())
+                        .unwrap_or_default();
+
+                    if slot == self.name {
+                        nodes.push(element.into());
+                        has_content = true;
+                    }
+                }
+                Err(node) => {
+                    if let Ok(text) = Text::try_from(node) {
+                        if self.name.is_empty() {
+                            has_content |= !text.data().trim().is_empty();
+                            nodes.push(text.into());
+                        }
+                    }
+                }
+            }
+        }
+
+        SlotAssignment { nodes, has_content }
+    }
+
+    /// Returns the nodes that are currently assigned to the slot.
+    pub fn assigned_nodes(&self) -> Vec<DynamicChildNode> {
+        self.assignment().nodes
+    }
+
+    /// Returns `true` if the slot currently has content (see [SlotAssignment::has_content]).
+    pub fn has_content(&self) -> bool {
+        self.assignment().has_content
+    }
+
+    /// Returns a stream of the assignments of the slot.
+    ///
+    /// The stream yields the current assignment immediately, and then a new assignment whenever
+    /// the nodes assigned to the slot change. Changes to the data of an assigned text node are
+    /// not observed, so whether the slot has content is only updated when the assigned nodes
+    /// change.
+    pub fn changes(&self) -> SlotChanges {
+        let state = Rc::new(RefCell::new(ChangeState {
+            last: None,
+            dirty: true,
+            waker: None,
+        }));
+
+        let callback = Closure::wrap(Box::new({
+            let state = state.clone();
+
+            move || {
+                let mut state = state.borrow_mut();
+
+                state.dirty = true;
+
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+            }
+        }) as Box<dyn FnMut()>);
+
+        let shadow_root = self.shadow_root.clone().unwrap_or(JsValue::NULL);
+        let unobserve = observe_assignment(&self.host, &shadow_root, callback.as_ref());
+
+        SlotChanges {
+            slot: self.clone(),
+            state,
+            unobserve,
+            _callback: callback,
+        }
+    }
+}
+
+/// The nodes assigned to a [Slot].
+#[derive(Clone)]
+pub struct SlotAssignment {
+    nodes: Vec<DynamicChildNode>,
+    has_content: bool,
+}
+
+impl SlotAssignment {
+    pub fn nodes(&self) -> &[DynamicChildNode] {
+        &self.nodes
+    }
+
+    /// Returns `true` if an element or a text node that is not all whitespace is assigned to the
+    /// slot.
+    pub fn has_content(&self) -> bool {
+        self.has_content
+    }
+
+    fn is_same(&self, other: &SlotAssignment) -> bool {
+        self.has_content == other.has_content
+            && self.nodes.len() == other.nodes.len()
+            && self.nodes.iter().zip(&other.nodes).all(|(a, b)| {
+                let a: &JsValue = a.as_ref();
+                let b: &JsValue = b.as_ref();
+
+                a == b
+            })
+    }
+}
+
+struct ChangeState {
+    last: Option<SlotAssignment>,
+    dirty: bool,
+    waker: Option<Waker>,
+}
+
+/// A stream of the assignments of a [Slot] (see [Slot::changes]).
+pub struct SlotChanges {
+    slot: Slot,
+    state: Rc<RefCell<ChangeState>>,
+    unobserve: Function,
+    _callback: Closure<dyn FnMut()>,
+}
+
+impl Stream for SlotChanges {
+    type Item = SlotAssignment;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut state = self.state.borrow_mut();
+
+        if state.dirty {
+            state.dirty = false;
+
+            let assignment = self.slot.assignment();
+            let changed = state
+                .last
+                .as_ref()
+                .map(|last| !last.is_same(&assignment))
+                .unwrap_or(true);
+
+            if changed {
+                state.last = Some(assignment.clone());
+
+                return Poll::Ready(Some(assignment));
+            }
+        }
+
+        state.waker = Some(cx.waker().clone());
+
+        Poll::Pending
+    }
+}
+
+impl Drop for SlotChanges {
+    fn drop(&mut self) {
+        self.unobserve.call0(&JsValue::UNDEFINED);
+    }
+}
@@ -0,0 +1,211 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+// Arwa does not support constructable stylesheets, so we bind these directly. A constructed sheet
+// can only be adopted by the shadow roots of the document it was constructed for, so we construct
+// sheets with the `CSSStyleSheet` constructor of the shadow root's window.
+#[wasm_bindgen(inline_js = r#"
+export function create_sheet(document, css) {
+    const sheet = new document.defaultView.CSSStyleSheet();
+
+    sheet.replaceSync(css);
+
+    return sheet;
+}
+
+export function adopt_sheets(root, removed, added) {
+    root.adoptedStyleSheets = root.adoptedStyleSheets
+        .filter((sheet) => !removed.includes(sheet))
+        .concat(added);
+}
+"#)]
+extern "C" {
+    fn create_sheet(document: &JsValue, css: &str) -> JsValue;
+
+    fn adopt_sheets(root: &JsValue, removed: &JsValue, added: &JsValue);
+}
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = CSSStyleSheet)]
+    type JsStyleSheet;
+
+    #[wasm_bindgen(method, js_name = replaceSync)]
+    fn replace_sync(this: &JsStyleSheet, text: &str);
+
+    #[wasm_bindgen(js_name = Array)]
+    type JsArray;
+
+    #[wasm_bindgen(constructor, js_class = "Array")]
+    fn new() -> JsArray;
+
+    #[wasm_bindgen(method)]
+    fn push(this: &JsArray, value: &JsValue) -> u32;
+
+    #[wasm_bindgen(js_namespace = Reflect, js_name = get)]
+    fn reflect_get(target: &JsValue, key: &str) -> JsValue;
+}
+
+struct SheetData {
+    css: String,
+    /// The `CSSStyleSheet` instances of the sheet, one for each document it was adopted in.
+    instances: Vec<(JsValue, JsValue)>,
+}
+
+/// A constructable `CSSStyleSheet` that can be shared by the shadow roots of many components.
+#[derive(Clone)]
+pub struct StyleSheet {
+    data: Rc<RefCell<SheetData>>,
+}
+
+impl StyleSheet {
+    pub fn new(css: &str) -> Self {
+        StyleSheet {
+            data: Rc::new(RefCell::new(SheetData {
+                css: css.to_string(),
+                instances: Vec::new(),
+            })),
+        }
+    }
+
+    /// Replaces the rules of the sheet with the rules in `css`, which updates every shadow root
+    /// that adopted the sheet.
+    pub fn replace(&self, css: &str) {
+        let mut data = self.data.borrow_mut();
+
+        data.css = css.to_string();
+
+        for (_, sheet) in data.instances.iter() {
+            sheet.unchecked_ref::<JsStyleSheet>().replace_sync(css);
+        }
+    }
+
+    /// Returns the instance of the sheet for the `document`, constructing it if necessary.
+    fn instance(&self, document: &JsValue) -> JsValue {
+        let mut data = self.data.borrow_mut();
+
+        if let Some((_, sheet)) = data.instances.iter().find(|(d, _)| d == document) {
+            return sheet.clone();
+        }
+
+        let sheet = create_sheet(document, &data.css);
+
+        data.instances.push((document.clone(), sheet.clone()));
+
+        sheet
+    }
+}
+
+/// A shadow root that adopted the sheets of a [StyleSheets] group; the shadow root stops
+/// receiving updates when this is dropped.
+pub(crate) struct AdoptedRoot {
+    root: JsValue,
+    group: Rc<RefCell<Group>>,
+    /// The sheet instances that the group added to the root's `adoptedStyleSheets`.
+    applied: RefCell<JsArray>,
+}
+
+impl AdoptedRoot {
+    /// Adopts the group's sheets again, e.g. after the shadow root's host was moved to another
+    /// document, for which the sheets need new instances.
+    pub(crate) fn reapply(&self) {
+        self.group.borrow().apply(self);
+    }
+}
+
+struct Group {
+    sheets: Vec<StyleSheet>,
+    roots: Vec<Weak<AdoptedRoot>>,
+}
+
+impl Group {
+    /// Replaces the sheets that this group previously added to the `root`'s `adoptedStyleSheets`
+    /// with the group's current sheets; sheets that were adopted by other means are kept.
+    fn apply(&self, root: &AdoptedRoot) {
+        let document = reflect_get(&root.root, "ownerDocument");
+        let added = JsArray::new();
+
+        for sheet in &self.sheets {
+            added.push(&sheet.instance(&document));
+        }
+
+        adopt_sheets(&root.root, &root.applied.borrow(), &added);
+        root.applied.replace(added);
+    }
+}
+
+/// A group of [StyleSheet]s that is adopted by the shadow root of every instance of a component
+/// (see [RegisterOptions::style_sheets](crate::RegisterOptions::style_sheets)).
+///
+/// The sheets are shared: each [StyleSheet] is a single `CSSStyleSheet` instance per document,
+/// regardless of the number of component instances. Use [set](StyleSheets::set) to swap the sheets
+/// of all instances at once (e.g. to change the theme). The group's sheets are appended to the
+/// `adoptedStyleSheets` of a shadow root, after any sheets the shadow root already adopted.
+#[derive(Clone)]
+pub struct StyleSheets {
+    group: Rc<RefCell<Group>>,
+}
+
+impl StyleSheets {
+    pub fn new(sheets: Vec<StyleSheet>) -> Self {
+        StyleSheets {
+            group: Rc::new(RefCell::new(Group {
+                sheets,
+                roots: Vec::new(),
+            })),
+        }
+    }
+
+    /// Returns the current sheets of the group.
+    pub fn sheets(&self) -> Vec<StyleSheet> {
+        self.group.borrow().sheets.clone()
+    }
+
+    /// Replaces the sheets of the group, which updates the shadow roots of all current instances.
+    pub fn set(&self, sheets: Vec<StyleSheet>) {
+        let mut group = self.group.borrow_mut();
+
+        group.sheets = sheets;
+        group.roots.retain(|root| root.strong_count() > 0);
+
+        for root in group.roots.iter().filter_map(|root| root.upgrade()) {
+            group.apply(&root);
+        }
+    }
+
+    /// Adopts the sheets of the group into the `shadow_root`.
+    pub(crate) fn adopt(&self, shadow_root: &JsValue) -> Rc<AdoptedRoot> {
+        let mut group = self.group.borrow_mut();
+        let root = Rc::new(AdoptedRoot {
+            root: shadow_root.clone(),
+            group: self.group.clone(),
+            applied: RefCell::new(JsArray::new()),
+        });
+
+        group.apply(&root);
+        group.roots.retain(|root| root.strong_count() > 0);
+        group.roots.push(Rc::downgrade(&root));
+
+        root
+    }
+}
+
+impl PartialEq for StyleSheets {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.group, &other.group)
+    }
+}
+
+impl Eq for StyleSheets {}
+
+impl fmt::Debug for StyleSheets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StyleSheets")
+            .field("sheets", &self.group.borrow().sheets.len())
+            .finish()
+    }
+}
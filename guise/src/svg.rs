@@ -0,0 +1,65 @@
+//! Element types for SVG elements.
+//!
+//! SVG elements are created with the methods of
+//! [ChildSvgElementExt](crate::vdom_builder_ext::ChildSvgElementExt); these types can also be used
+//! with an [ElementRef](crate::ElementRef).
+
+use wasm_bindgen::prelude::*;
+
+/// Implemented for the SVG element types that correspond to a specific tag name.
+pub trait KnownSvgElement {
+    const TAG_NAME: &'static str;
+}
+
+macro_rules! svg_element {
+    ($tpe:ident, $js_name:ident, $tag_name:literal) => {
+        #[wasm_bindgen]
+        extern "C" {
+            #[wasm_bindgen(js_name = $js_name)]
+            #[derive(Clone, Debug)]
+            pub type $tpe;
+        }
+
+        impl KnownSvgElement for $tpe {
+            const TAG_NAME: &'static str = $tag_name;
+        }
+    };
+}
+
+svg_element!(SvgSvgElement, SVGSVGElement, "svg");
+svg_element!(SvgGElement, SVGGElement, "g");
+svg_element!(SvgDefsElement, SVGDefsElement, "defs");
+svg_element!(SvgSymbolElement, SVGSymbolElement, "symbol");
+svg_element!(SvgUseElement, SVGUseElement, "use");
+svg_element!(SvgTitleElement, SVGTitleElement, "title");
+svg_element!(SvgDescElement, SVGDescElement, "desc");
+svg_element!(SvgPathElement, SVGPathElement, "path");
+svg_element!(SvgCircleElement, SVGCircleElement, "circle");
+svg_element!(SvgEllipseElement, SVGEllipseElement, "ellipse");
+svg_element!(SvgLineElement, SVGLineElement, "line");
+svg_element!(SvgPolygonElement, SVGPolygonElement, "polygon");
+svg_element!(SvgPolylineElement, SVGPolylineElement, "polyline");
+svg_element!(SvgRectElement, SVGRectElement, "rect");
+svg_element!(SvgTextElement, SVGTextElement, "text");
+svg_element!(SvgTspanElement, SVGTSpanElement, "tspan");
+svg_element!(SvgImageElement, SVGImageElement, "image");
+svg_element!(
+    SvgForeignObjectElement,
+    SVGForeignObjectElement,
+    "foreignObject"
+);
+svg_element!(
+    SvgLinearGradientElement,
+    SVGLinearGradientElement,
+    "linearGradient"
+);
+svg_element!(
+    SvgRadialGradientElement,
+    SVGRadialGradientElement,
+    "radialGradient"
+);
+svg_element!(SvgStopElement, SVGStopElement, "stop");
+svg_element!(SvgClipPathElement, SVGClipPathElement, "clipPath");
+svg_element!(SvgMaskElement, SVGMaskElement, "mask");
+svg_element!(SvgPatternElement, SVGPatternElement, "pattern");
+svg_element!(SvgMarkerElement, SVGMarkerElement, "marker");
@@ -0,0 +1,17 @@
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console, js_name = warn)]
+    fn console_warn(message: &str);
+}
+
+/// Logs a warning to the browser console; outside of a browser (e.g. when patching a
+/// [MemoryBackend](crate::backend::MemoryBackend) in a test), the warning is printed to stderr.
+pub(crate) fn warn(message: &str) {
+    if cfg!(target_arch = "wasm32") {
+        console_warn(message);
+    } else {
+        eprintln!("warning: {}", message);
+    }
+}
@@ -1,7 +1,10 @@
+use std::any::Any;
+use std::cell::RefCell;
 use std::fmt::Debug;
 use std::marker;
+use std::rc::Rc;
 
-use arwa::dom::{DynamicElement, Name};
+use arwa::dom::{DynamicElement, Name, ShadowRootOptions, StaticallyParsedName};
 use arwa::event::{EventTarget, TypedEvent};
 use arwa::html::{CustomElementName, KnownElement};
 use bumpalo::collections::Vec as BumpVec;
@@ -9,76 +12,222 @@ use bumpalo::Bump;
 use futures::Sink;
 use ouroboros::self_referencing;
 
+use crate::component::{AnyInstance, Component, Instance};
+use crate::element_ref::RawElementRef;
+use crate::listener::ListenerOptions;
+use crate::render_to_string::{render_to_string, render_to_string_with_shadow_root};
+use crate::scoped_style::ScopedStyle;
 use crate::sink_spawner::SinkSpawner;
+use crate::svg::KnownSvgElement;
 use crate::vdom_builder_ext::{
-    child_known_element_ext_seal, sink_ui_event_ext_seal, ChildKnownElementExt, SinkUIEventExt,
+    child_known_element_ext_seal, child_svg_element_ext_seal, sink_ui_event_ext_seal,
+    ChildKnownElementExt, ChildSvgElementExt, SinkUIEventExt,
 };
 use crate::ElementRef;
-use crate::element_ref::RawElementRef;
+
+/// The namespace of an element or attribute.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Namespace {
+    Html,
+    Svg,
+    MathMl,
+    XLink,
+    Xml,
+}
+
+impl Namespace {
+    pub fn uri(&self) -> &'static str {
+        match self {
+            Namespace::Html => "http://www.w3.org/1999/xhtml",
+            Namespace::Svg => "http://www.w3.org/2000/svg",
+            Namespace::MathMl => "http://www.w3.org/1998/Math/MathML",
+            Namespace::XLink => "http://www.w3.org/1999/xlink",
+            Namespace::Xml => "http://www.w3.org/XML/1998/namespace",
+        }
+    }
+
+    /// The namespace identified by the given `uri`, if it is one of the known namespaces.
+    pub(crate) fn from_uri(uri: &str) -> Option<Self> {
+        [
+            Namespace::Html,
+            Namespace::Svg,
+            Namespace::MathMl,
+            Namespace::XLink,
+            Namespace::Xml,
+        ]
+        .into_iter()
+        .find(|namespace| namespace.uri() == uri)
+    }
+
+    /// The namespace in which the HTML parser would place an element with the given `tag_name`
+    /// inside a parent that establishes the given `context` namespace for its children (see
+    /// [Namespace::context_of]).
+    fn for_child(context: Namespace, tag_name: &Name) -> Self {
+        let tag_name: &str = tag_name.as_ref();
+
+        match context {
+            Namespace::Svg => Namespace::Svg,
+            Namespace::MathMl => Namespace::MathMl,
+            _ if unicase::eq_ascii(tag_name, "svg") => Namespace::Svg,
+            _ if unicase::eq_ascii(tag_name, "math") => Namespace::MathMl,
+            _ => Namespace::Html,
+        }
+    }
+
+    /// The namespace context that the `element` establishes for its children.
+    ///
+    /// Children of an SVG `foreignObject` element are treated as if they had an HTML parent.
+    fn context_of(element: &Element) -> Self {
+        let tag_name: &str = element.tag_name.as_ref();
+
+        if element.namespace == Namespace::Svg && unicase::eq_ascii(tag_name, "foreignObject") {
+            Namespace::Html
+        } else {
+            element.namespace
+        }
+    }
+}
+
+/// The value of a DOM property (see `ElementBuilder::prop`).
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PropertyValue<'a> {
+    Bool(bool),
+    Number(f64),
+    String(&'a str),
+}
+
+impl From<bool> for PropertyValue<'_> {
+    fn from(value: bool) -> Self {
+        PropertyValue::Bool(value)
+    }
+}
+
+impl From<f64> for PropertyValue<'_> {
+    fn from(value: f64) -> Self {
+        PropertyValue::Number(value)
+    }
+}
+
+impl From<i32> for PropertyValue<'_> {
+    fn from(value: i32) -> Self {
+        PropertyValue::Number(value as f64)
+    }
+}
+
+impl From<u32> for PropertyValue<'_> {
+    fn from(value: u32) -> Self {
+        PropertyValue::Number(value as f64)
+    }
+}
+
+impl<'a> From<&'a str> for PropertyValue<'a> {
+    fn from(value: &'a str) -> Self {
+        PropertyValue::String(value)
+    }
+}
+
+pub(crate) type OnRendered = Box<dyn FnOnce(&DynamicElement)>;
+
+thread_local! {
+    /// Collects the `on_rendered` callbacks of the memos and components that are rendered inside
+    /// of [collect_on_rendered]; `None` outside of [collect_on_rendered].
+    static FORWARDED_ON_RENDERED: RefCell<Option<Vec<OnRendered>>> = RefCell::new(None);
+}
+
+/// Calls `f` and returns its result, together with the `on_rendered` callbacks of the memos and
+/// components that were rendered during the call (see [forward_on_rendered]).
+pub(crate) fn collect_on_rendered<F, R>(f: F) -> (R, Vec<OnRendered>)
+where
+    F: FnOnce() -> R,
+{
+    let outer = FORWARDED_ON_RENDERED.with(|forwarded| forwarded.replace(Some(Vec::new())));
+    let result = f();
+    let collected = FORWARDED_ON_RENDERED.with(|forwarded| forwarded.replace(outer));
+
+    (result, collected.unwrap_or_default())
+}
+
+/// Forwards the `on_rendered` callback of a virtual DOM rendered by a memo or component to the
+/// enclosing [collect_on_rendered] call, so that it is called once the patch that rendered it
+/// completes.
+pub(crate) fn forward_on_rendered(vdom: &mut VDom) {
+    if let Some(on_rendered) = vdom.on_rendered.take() {
+        FORWARDED_ON_RENDERED.with(|forwarded| {
+            if let Some(forwarded) = forwarded.borrow_mut().as_mut() {
+                forwarded.push(on_rendered);
+            }
+        });
+    }
+}
 
 pub struct VDom {
     pub(crate) internal: VDomInternal,
-    pub(crate) on_rendered: Option<Box<dyn FnOnce(&DynamicElement)>>,
+    pub(crate) on_rendered: Option<OnRendered>,
+    scoped_style: Option<ScopedStyle>,
+    /// The namespace context of the top-level nodes (see [Namespace::for_child]); the context of
+    /// the parent of a memo or component.
+    context: Namespace,
 }
 
 impl VDom {
     pub fn new() -> Self {
+        VDom::with_context(Namespace::Html)
+    }
+
+    /// Creates an empty virtual DOM for the nodes of a memo or component, which are placed in the
+    /// parent's namespace `context`.
+    pub(crate) fn with_context(context: Namespace) -> Self {
         let alloc = Bump::new();
 
         VDom {
             internal: VDomInternal::new(alloc, |alloc| &alloc, |alloc| BumpVec::new_in(alloc)),
             on_rendered: None,
+            scoped_style: None,
+            context,
         }
     }
 
-    pub fn text(&mut self, text: &str) {
+    fn with_children<F>(&mut self, f: F)
+    where
+        F: FnOnce(ChildrenBuilder),
+    {
         self.internal.with_mut(|fields| {
-            let text = fields.alloc_ref.alloc_str(text);
-
-            fields.nodes.push(Node::Text(text))
+            f(ChildrenBuilder {
+                alloc: *fields.alloc_ref,
+                context: self.context,
+                nodes: fields.nodes,
+            })
         });
     }
 
-    fn child_internal<E, F>(&mut self, tag_name: Name, is: Option<CustomElementName>, f: F)
+    pub fn text(&mut self, text: &str) {
+        self.with_children(|mut children| children.text(text));
+    }
+
+    /// Adds a child element with the given `tag_name`.
+    ///
+    /// The element is placed in the SVG namespace if the `tag_name` is `svg`, in the MathML
+    /// namespace if the `tag_name` is `math`, and in the HTML namespace otherwise.
+    pub fn child<F>(&mut self, tag_name: Name, f: F)
     where
-        E: EventTarget,
-        F: FnOnce(ElementBuilder<E>),
+        F: FnOnce(ElementBuilder<DynamicElement>),
     {
-        self.internal.with_mut(|fields| {
-            let tag_name = fields.alloc_ref.alloc(tag_name);
-            let is = is.map(|n| fields.alloc_ref.alloc(n));
-
-            let mut element = Element {
-                tag_name,
-                is,
-                attributes: BumpVec::new_in(fields.alloc_ref),
-                children: BumpVec::new_in(fields.alloc_ref),
-                sink_spawners: BumpVec::new_in(fields.alloc_ref),
-                element_refs: BumpVec::new_in(fields.alloc_ref),
-            };
-
-            f(ElementBuilder {
-                alloc: fields.alloc_ref,
-                element: &mut element,
-                _marker: Default::default(),
-            });
-
-            fields.nodes.push(Node::Element(element))
-        });
+        self.with_children(|mut children| children.child(tag_name, f));
     }
 
-    pub fn child<F>(&mut self, tag_name: Name, f: F)
+    /// Adds a child element with the given `tag_name` in the given `namespace`.
+    pub fn child_ns<F>(&mut self, namespace: Namespace, tag_name: Name, f: F)
     where
         F: FnOnce(ElementBuilder<DynamicElement>),
     {
-        self.child_internal(tag_name, None, f);
+        self.with_children(|mut children| children.element(namespace, tag_name, None, f));
     }
 
     pub fn child_customized<F>(&mut self, tag_name: Name, is: CustomElementName, f: F)
     where
         F: FnOnce(ElementBuilder<DynamicElement>),
     {
-        self.child_internal(tag_name, Some(is), f);
+        self.with_children(|mut children| children.element(Namespace::Html, tag_name, Some(is), f));
     }
 
     pub fn on_rendered<F>(&mut self, f: F)
@@ -88,11 +237,100 @@ impl VDom {
         self.on_rendered = Some(Box::new(f))
     }
 
-    pub(crate) fn with_nodes_mut<F>(&mut self, f: F)
+    /// Scopes the `style` to the elements of this virtual DOM (see [ScopedStyle]).
+    ///
+    /// Applies to all elements of this virtual DOM, including the elements rendered by its memos
+    /// and components, unless the memo or component renders a virtual DOM with a scoped style of
+    /// its own.
+    pub fn set_scoped_style(&mut self, style: ScopedStyle) {
+        self.scoped_style = Some(style);
+    }
+
+    pub(crate) fn scoped_style(&self) -> Option<ScopedStyle> {
+        self.scoped_style
+    }
+
+    /// Serializes this virtual DOM to an HTML string.
+    ///
+    /// See [render_to_string] for details.
+    pub fn to_html(&self) -> String {
+        render_to_string(self)
+    }
+
+    /// Serializes this virtual DOM to an HTML string, wrapped in a declarative shadow root.
+    ///
+    /// See [render_to_string_with_shadow_root] for details.
+    pub fn to_html_with_shadow_root(&self, shadow_root_options: ShadowRootOptions) -> String {
+        render_to_string_with_shadow_root(self, shadow_root_options)
+    }
+
+    /// Adds a fragment: a group of sibling nodes that is not wrapped in an element.
+    ///
+    /// The nodes of the fragment are placed directly into the parent of the fragment. A fragment
+    /// may contain any number of nodes (including none) and can be nested anywhere a node can be
+    /// added, which allows helper functions that take a [FragmentBuilder] to contribute several
+    /// nodes, or no nodes at all, to their caller.
+    pub fn fragment<F>(&mut self, f: F)
     where
-        F: FnOnce(&mut [Node]),
+        F: FnOnce(FragmentBuilder),
     {
-        self.internal.with_nodes_mut(|nodes| f(nodes));
+        self.with_children(|mut children| children.fragment(f));
+    }
+
+    /// Adds a memoized region of child nodes.
+    ///
+    /// The `render` function is not called when the virtual DOM is built; instead, it is called
+    /// when the virtual DOM is patched, and only if the `deps` do not compare equal to the `deps`
+    /// of the memo at the same position in the previous virtual DOM. If the `deps` are equal, the
+    /// previously rendered nodes are carried over into this virtual DOM and their DOM nodes are
+    /// left untouched.
+    ///
+    /// The nodes are placed in the namespace of the memo's parent (e.g. a memo inside an `<svg>`
+    /// element renders SVG elements). An `on_rendered` callback registered inside the `render`
+    /// function is called after the patch in which the memo was rendered.
+    pub fn memo<D, F>(&mut self, deps: D, render: F)
+    where
+        D: PartialEq + 'static,
+        F: Fn(&D, &mut VDom) + 'static,
+    {
+        self.with_children(|mut children| children.memo(deps, render));
+    }
+
+    /// Adds a [Component], rendered with the given `props`.
+    ///
+    /// Like a memo, the component is rendered when the virtual DOM is patched, so that it can
+    /// carry over the state of the component of the same type at the same position in the
+    /// previous virtual DOM.
+    pub fn component<C>(&mut self, component: C, props: C::Props)
+    where
+        C: Component,
+    {
+        self.with_children(|mut children| children.component(None, component, props));
+    }
+
+    /// Adds a [Component] with a key that identifies it among its siblings.
+    ///
+    /// The component carries over the state of the component of the same type with the same key
+    /// in the previous virtual DOM, regardless of its position; see [ElementBuilder::key].
+    pub fn keyed_component<C>(&mut self, key: &str, component: C, props: C::Props)
+    where
+        C: Component,
+    {
+        self.with_children(|mut children| children.component(Some(key), component, props));
+    }
+
+    pub(crate) fn with_nodes<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&[Node]) -> R,
+    {
+        self.internal.with_nodes(|nodes| f(nodes))
+    }
+
+    pub(crate) fn with_nodes_mut<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut [Node]) -> R,
+    {
+        self.internal.with_nodes_mut(|nodes| f(nodes))
     }
 }
 
@@ -103,7 +341,22 @@ impl ChildKnownElementExt for VDom {
         T: KnownElement + EventTarget,
         F: FnOnce(ElementBuilder<T>),
     {
-        self.child_internal(T::TAG_NAME.clone(), None, f)
+        self.with_children(|mut children| {
+            children.element(Namespace::Html, T::TAG_NAME.clone(), None, f)
+        })
+    }
+}
+
+impl child_svg_element_ext_seal::Seal for VDom {}
+impl ChildSvgElementExt for VDom {
+    fn child_svg_element<T, F>(&mut self, f: F)
+    where
+        T: KnownSvgElement,
+        F: FnOnce(ElementBuilder<T>),
+    {
+        let tag_name = Name::from_statically_parsed(StaticallyParsedName { name: T::TAG_NAME });
+
+        self.with_children(|mut children| children.element(Namespace::Svg, tag_name, None, f))
     }
 }
 
@@ -124,62 +377,167 @@ pub struct ElementBuilder<'a, 'b, E> {
 }
 
 impl<'a, 'b, E> ElementBuilder<'a, 'b, E> {
+    /// Tags the element with a key that identifies it among its siblings.
+    ///
+    /// A keyed element is matched against the sibling with the same key in the previous render,
+    /// rather than against the sibling at the same index, and its DOM node is moved rather than
+    /// patched in place when its position changes. Keys should be unique among siblings.
+    pub fn key(&mut self, key: &str) {
+        let key = self.alloc.alloc_str(key);
+
+        self.element.key = Some(key);
+    }
+
     pub fn attr(&mut self, name: Name, value: &str) {
         let name = self.alloc.alloc(name);
         let value = self.alloc.alloc_str(value);
 
-        self.element.attributes.push(Attribute { name, value });
+        self.element.attributes.push(Attribute {
+            namespace: None,
+            name,
+            value,
+        });
+    }
+
+    /// Sets a namespaced attribute, e.g. `xlink:href`.
+    ///
+    /// The `name` is the qualified name of the attribute, including its prefix.
+    pub fn attr_ns(&mut self, namespace: Namespace, name: Name, value: &str) {
+        let name = self.alloc.alloc(name);
+        let value = self.alloc.alloc_str(value);
+
+        self.element.attributes.push(Attribute {
+            namespace: Some(namespace),
+            name,
+            value,
+        });
     }
 
     pub fn boolean_attr(&mut self, name: Name) {
         let name = self.alloc.alloc(name);
 
-        self.element.attributes.push(Attribute { name, value: "" });
+        self.element.attributes.push(Attribute {
+            namespace: None,
+            name,
+            value: "",
+        });
     }
 
-    pub fn text(&mut self, text: &str) {
-        let text = self.alloc.alloc_str(text);
+    /// Sets a DOM property on the element, e.g. the `value` of an `<input>` element.
+    ///
+    /// Unlike an attribute, a property is compared against the live value of the DOM element
+    /// every time the element is patched, so that it is restored if it was changed by the user.
+    /// Properties are set after the element's children have been patched, e.g. so that the
+    /// `value` of a `<select>` element can refer to one of its `<option>` children. A property
+    /// that is no longer set on the element is left at its current value.
+    ///
+    /// Properties are not represented in the output of [render_to_string].
+    pub fn prop<'v>(&mut self, name: &str, value: impl Into<PropertyValue<'v>>) {
+        let name = self.alloc.alloc_str(name);
+        let value = match value.into() {
+            PropertyValue::String(value) => PropertyValue::String(self.alloc.alloc_str(value)),
+            value => value,
+        };
 
-        self.element.children.push(Node::Text(text));
+        self.element.properties.push(Property { name, value });
     }
 
-    fn child_internal<T, F>(&mut self, tag_name: Name, is: Option<CustomElementName>, f: F)
+    /// Passes typed `props` to the element, if it is a Guise element registered with
+    /// [register_with_props](crate::register_with_props) (or one of its variants).
+    ///
+    /// The element receives the props through its [PropsChanged](crate::PropsChanged) stream.
+    /// The props are only passed on again when they do not compare equal to the props of the
+    /// previous render. Props that are no longer set on the element are not reset.
+    ///
+    /// Props are not represented in the output of [render_to_string].
+    pub fn props<P>(&mut self, props: P)
     where
-        F: FnOnce(ElementBuilder<T>),
+        P: PartialEq + 'static,
     {
-        let tag_name = self.alloc.alloc(tag_name);
-        let is = is.map(|n| self.alloc.alloc(n));
-
-        let mut element = Element {
-            tag_name,
-            is,
-            attributes: BumpVec::new_in(self.alloc),
-            children: BumpVec::new_in(self.alloc),
-            sink_spawners: BumpVec::new_in(self.alloc),
-            element_refs: BumpVec::new_in(self.alloc),
-        };
+        self.element.props = Some(Props::new(props));
+    }
 
-        f(ElementBuilder {
+    fn children(&mut self) -> ChildrenBuilder<'_, 'b> {
+        ChildrenBuilder {
             alloc: self.alloc,
-            element: &mut element,
-            _marker: Default::default(),
-        });
+            context: Namespace::context_of(self.element),
+            nodes: &mut self.element.children,
+        }
+    }
 
-        self.element.children.push(Node::Element(element));
+    pub fn text(&mut self, text: &str) {
+        self.children().text(text);
     }
 
+    /// Adds a child element with the given `tag_name`.
+    ///
+    /// The element is placed in the namespace in which the HTML parser would place it: children
+    /// of SVG elements (other than `foreignObject`) are placed in the SVG namespace, children of
+    /// MathML elements in the MathML namespace. Otherwise, `svg` elements are placed in the SVG
+    /// namespace, `math` elements in the MathML namespace and all other elements in the HTML
+    /// namespace.
     pub fn child<F>(&mut self, tag_name: Name, f: F)
     where
         F: FnOnce(ElementBuilder<DynamicElement>),
     {
-        self.child_internal(tag_name, None, f);
+        self.children().child(tag_name, f);
+    }
+
+    /// Adds a child element with the given `tag_name` in the given `namespace`.
+    pub fn child_ns<F>(&mut self, namespace: Namespace, tag_name: Name, f: F)
+    where
+        F: FnOnce(ElementBuilder<DynamicElement>),
+    {
+        self.children().element(namespace, tag_name, None, f);
     }
 
     pub fn child_customized<F>(&mut self, tag_name: Name, is: CustomElementName, f: F)
     where
         F: FnOnce(ElementBuilder<DynamicElement>),
     {
-        self.child_internal(tag_name, Some(is), f);
+        self.children()
+            .element(Namespace::Html, tag_name, Some(is), f);
+    }
+
+    /// Adds a fragment: a group of sibling nodes that is not wrapped in an element.
+    ///
+    /// See [VDom::fragment] for details.
+    pub fn fragment<F>(&mut self, f: F)
+    where
+        F: FnOnce(FragmentBuilder),
+    {
+        self.children().fragment(f);
+    }
+
+    /// Adds a memoized region of child nodes.
+    ///
+    /// See [VDom::memo] for details.
+    pub fn memo<D, F>(&mut self, deps: D, render: F)
+    where
+        D: PartialEq + 'static,
+        F: Fn(&D, &mut VDom) + 'static,
+    {
+        self.children().memo(deps, render);
+    }
+
+    /// Adds a [Component], rendered with the given `props`.
+    ///
+    /// See [VDom::component] for details.
+    pub fn component<C>(&mut self, component: C, props: C::Props)
+    where
+        C: Component,
+    {
+        self.children().component(None, component, props);
+    }
+
+    /// Adds a [Component] with a key that identifies it among its siblings.
+    ///
+    /// See [VDom::keyed_component] for details.
+    pub fn keyed_component<C>(&mut self, key: &str, component: C, props: C::Props)
+    where
+        C: Component,
+    {
+        self.children().component(Some(key), component, props);
     }
 
     pub fn sink_event<T, S>(&mut self, sink: S)
@@ -189,7 +547,21 @@ impl<'a, 'b, E> ElementBuilder<'a, 'b, E> {
         S: Sink<T> + 'static,
         S::Error: Debug,
     {
-        self.element.sink_spawners.push(SinkSpawner::new(sink));
+        self.sink_event_with_options(sink, ListenerOptions::default());
+    }
+
+    /// Like `sink_event`, but the `sink` receives the events through a listener with the given
+    /// `options` (e.g. a passive `wheel` listener, or a capture-phase listener).
+    pub fn sink_event_with_options<T, S>(&mut self, sink: S, options: ListenerOptions)
+    where
+        E: EventTarget,
+        T: TypedEvent<CurrentTarget = E> + 'static,
+        S: Sink<T> + 'static,
+        S::Error: Debug,
+    {
+        self.element
+            .sink_spawners
+            .push(SinkSpawner::new(sink, options));
     }
 
     pub fn element_ref(&mut self, element_ref: ElementRef<E>) {
@@ -204,38 +576,312 @@ impl<'a, 'b, E> ChildKnownElementExt for ElementBuilder<'a, 'b, E> {
         T: KnownElement + EventTarget,
         F: FnOnce(ElementBuilder<T>),
     {
-        self.child_internal(T::TAG_NAME.clone(), None, f)
+        self.children()
+            .element(Namespace::Html, T::TAG_NAME.clone(), None, f)
+    }
+}
+
+impl<'a, 'b, E> child_svg_element_ext_seal::Seal for ElementBuilder<'a, 'b, E> {}
+impl<'a, 'b, E> ChildSvgElementExt for ElementBuilder<'a, 'b, E> {
+    fn child_svg_element<T, F>(&mut self, f: F)
+    where
+        T: KnownSvgElement,
+        F: FnOnce(ElementBuilder<T>),
+    {
+        let tag_name = Name::from_statically_parsed(StaticallyParsedName { name: T::TAG_NAME });
+
+        self.children().element(Namespace::Svg, tag_name, None, f)
     }
 }
 
 impl<'a, 'b, E> sink_ui_event_ext_seal::Seal<E> for ElementBuilder<'a, 'b, E> {
-    fn sink_event<T, S>(&mut self, sink: S)
+    fn sink_event<T, S>(&mut self, sink: S, options: ListenerOptions)
     where
         E: EventTarget,
         T: TypedEvent<CurrentTarget = E> + 'static,
         S: Sink<T> + 'static,
         S::Error: Debug,
     {
-        ElementBuilder::sink_event(self, sink);
+        ElementBuilder::sink_event_with_options(self, sink, options);
     }
 }
 impl<'a, 'b, E> SinkUIEventExt<E> for ElementBuilder<'a, 'b, E> {}
 
+pub struct FragmentBuilder<'a, 'b> {
+    alloc: &'b Bump,
+    context: Namespace,
+    fragment: &'a mut Fragment<'b>,
+}
+
+impl<'a, 'b> FragmentBuilder<'a, 'b> {
+    /// Tags the fragment with a key that identifies it among its siblings.
+    ///
+    /// See [ElementBuilder::key] for details; the DOM nodes of a keyed fragment are moved as a
+    /// group.
+    pub fn key(&mut self, key: &str) {
+        let key = self.alloc.alloc_str(key);
+
+        self.fragment.key = Some(key);
+    }
+
+    fn children(&mut self) -> ChildrenBuilder<'_, 'b> {
+        ChildrenBuilder {
+            alloc: self.alloc,
+            context: self.context,
+            nodes: &mut self.fragment.children,
+        }
+    }
+
+    pub fn text(&mut self, text: &str) {
+        self.children().text(text);
+    }
+
+    /// Adds a child element with the given `tag_name`.
+    ///
+    /// The element is placed in the namespace in which it would be placed if it were added
+    /// directly to the parent of the fragment (see [ElementBuilder::child]).
+    pub fn child<F>(&mut self, tag_name: Name, f: F)
+    where
+        F: FnOnce(ElementBuilder<DynamicElement>),
+    {
+        self.children().child(tag_name, f);
+    }
+
+    /// Adds a child element with the given `tag_name` in the given `namespace`.
+    pub fn child_ns<F>(&mut self, namespace: Namespace, tag_name: Name, f: F)
+    where
+        F: FnOnce(ElementBuilder<DynamicElement>),
+    {
+        self.children().element(namespace, tag_name, None, f);
+    }
+
+    pub fn child_customized<F>(&mut self, tag_name: Name, is: CustomElementName, f: F)
+    where
+        F: FnOnce(ElementBuilder<DynamicElement>),
+    {
+        self.children()
+            .element(Namespace::Html, tag_name, Some(is), f);
+    }
+
+    /// Adds a nested fragment.
+    pub fn fragment<F>(&mut self, f: F)
+    where
+        F: FnOnce(FragmentBuilder),
+    {
+        self.children().fragment(f);
+    }
+
+    /// Adds a memoized region of child nodes.
+    ///
+    /// See [VDom::memo] for details.
+    pub fn memo<D, F>(&mut self, deps: D, render: F)
+    where
+        D: PartialEq + 'static,
+        F: Fn(&D, &mut VDom) + 'static,
+    {
+        self.children().memo(deps, render);
+    }
+
+    /// Adds a [Component], rendered with the given `props`.
+    ///
+    /// See [VDom::component] for details.
+    pub fn component<C>(&mut self, component: C, props: C::Props)
+    where
+        C: Component,
+    {
+        self.children().component(None, component, props);
+    }
+
+    /// Adds a [Component] with a key that identifies it among its siblings.
+    ///
+    /// See [VDom::keyed_component] for details.
+    pub fn keyed_component<C>(&mut self, key: &str, component: C, props: C::Props)
+    where
+        C: Component,
+    {
+        self.children().component(Some(key), component, props);
+    }
+}
+
+impl<'a, 'b> child_known_element_ext_seal::Seal for FragmentBuilder<'a, 'b> {}
+impl<'a, 'b> ChildKnownElementExt for FragmentBuilder<'a, 'b> {
+    fn child_known_element<T, F>(&mut self, f: F)
+    where
+        T: KnownElement + EventTarget,
+        F: FnOnce(ElementBuilder<T>),
+    {
+        self.children()
+            .element(Namespace::Html, T::TAG_NAME.clone(), None, f)
+    }
+}
+
+impl<'a, 'b> child_svg_element_ext_seal::Seal for FragmentBuilder<'a, 'b> {}
+impl<'a, 'b> ChildSvgElementExt for FragmentBuilder<'a, 'b> {
+    fn child_svg_element<T, F>(&mut self, f: F)
+    where
+        T: KnownSvgElement,
+        F: FnOnce(ElementBuilder<T>),
+    {
+        let tag_name = Name::from_statically_parsed(StaticallyParsedName { name: T::TAG_NAME });
+
+        self.children().element(Namespace::Svg, tag_name, None, f)
+    }
+}
+
+/// Adds child nodes to the children of a [VDom], an element or a fragment; the builders delegate
+/// to this, so that they build their child nodes in the same way.
+struct ChildrenBuilder<'a, 'b> {
+    alloc: &'b Bump,
+    /// The namespace context of the children (see [Namespace::for_child]).
+    context: Namespace,
+    nodes: &'a mut BumpVec<'b, Node<'b>>,
+}
+
+impl<'a, 'b> ChildrenBuilder<'a, 'b> {
+    fn text(&mut self, text: &str) {
+        let text = self.alloc.alloc_str(text);
+
+        self.nodes.push(Node::Text(text));
+    }
+
+    fn child<F>(&mut self, tag_name: Name, f: F)
+    where
+        F: FnOnce(ElementBuilder<DynamicElement>),
+    {
+        let namespace = Namespace::for_child(self.context, &tag_name);
+
+        self.element(namespace, tag_name, None, f);
+    }
+
+    fn element<T, F>(
+        &mut self,
+        namespace: Namespace,
+        tag_name: Name,
+        is: Option<CustomElementName>,
+        f: F,
+    ) where
+        F: FnOnce(ElementBuilder<T>),
+    {
+        let tag_name = self.alloc.alloc(tag_name);
+        let is = is.map(|n| self.alloc.alloc(n));
+
+        let mut element = Element {
+            namespace,
+            tag_name,
+            is,
+            key: None,
+            attributes: BumpVec::new_in(self.alloc),
+            properties: BumpVec::new_in(self.alloc),
+            props: None,
+            children: BumpVec::new_in(self.alloc),
+            sink_spawners: BumpVec::new_in(self.alloc),
+            element_refs: BumpVec::new_in(self.alloc),
+        };
+
+        f(ElementBuilder {
+            alloc: self.alloc,
+            element: &mut element,
+            _marker: Default::default(),
+        });
+
+        self.nodes.push(Node::Element(element));
+    }
+
+    fn fragment<F>(&mut self, f: F)
+    where
+        F: FnOnce(FragmentBuilder),
+    {
+        let mut fragment = Fragment {
+            key: None,
+            children: BumpVec::new_in(self.alloc),
+        };
+
+        f(FragmentBuilder {
+            alloc: self.alloc,
+            context: self.context,
+            fragment: &mut fragment,
+        });
+
+        self.nodes.push(Node::Fragment(fragment));
+    }
+
+    fn memo<D, F>(&mut self, deps: D, render: F)
+    where
+        D: PartialEq + 'static,
+        F: Fn(&D, &mut VDom) + 'static,
+    {
+        self.nodes
+            .push(Node::Memo(Memo::new(deps, render, self.context)));
+    }
+
+    fn component<C>(&mut self, key: Option<&str>, component: C, props: C::Props)
+    where
+        C: Component,
+    {
+        let key = key.map(|key| &*self.alloc.alloc_str(key));
+
+        self.nodes.push(Node::Component(ComponentNode::new(
+            key,
+            component,
+            props,
+            self.context,
+        )));
+    }
+}
+
 pub(crate) enum Node<'a> {
     Text(&'a str),
     Element(Element<'a>),
+    Fragment(Fragment<'a>),
+    Memo(Memo),
+    Component(ComponentNode<'a>),
+}
+
+impl<'a> Node<'a> {
+    pub(crate) fn key(&self) -> Option<&'a str> {
+        match self {
+            Node::Element(element) => element.key,
+            Node::Fragment(fragment) => fragment.key,
+            Node::Component(component) => component.key,
+            _ => None,
+        }
+    }
+
+    /// Whether `self` may be patched into `other` without having to replace the DOM node.
+    pub(crate) fn is_compatible(&self, other: &Node) -> bool {
+        match (self, other) {
+            (Node::Text(_), Node::Text(_)) => true,
+            (Node::Fragment(_), Node::Fragment(_)) => true,
+            (Node::Memo(_), Node::Memo(_)) => true,
+            (Node::Component(a), Node::Component(b)) => {
+                a.instance.component_type_id() == b.instance.component_type_id()
+            }
+            (Node::Element(a), Node::Element(b)) => {
+                a.namespace == b.namespace && a.tag_name == b.tag_name && a.is() == b.is()
+            }
+            _ => false,
+        }
+    }
 }
 
 pub(crate) struct Element<'a> {
+    namespace: Namespace,
     tag_name: &'a Name,
     is: Option<&'a mut CustomElementName>,
+    key: Option<&'a str>,
     attributes: BumpVec<'a, Attribute<'a>>,
+    properties: BumpVec<'a, Property<'a>>,
+    props: Option<Props>,
     children: BumpVec<'a, Node<'a>>,
     sink_spawners: BumpVec<'a, SinkSpawner>,
     element_refs: BumpVec<'a, RawElementRef>,
 }
 
 impl<'a> Element<'a> {
+    pub(crate) fn namespace(&self) -> Namespace {
+        self.namespace
+    }
+
     pub(crate) fn tag_name(&self) -> &Name {
         &self.tag_name
     }
@@ -248,6 +894,18 @@ impl<'a> Element<'a> {
         &self.attributes
     }
 
+    pub(crate) fn properties(&self) -> &[Property<'a>] {
+        &self.properties
+    }
+
+    pub(crate) fn props(&self) -> Option<&Props> {
+        self.props.as_ref()
+    }
+
+    pub(crate) fn children(&self) -> &[Node<'a>] {
+        &self.children
+    }
+
     pub(crate) fn children_mut(&mut self) -> &mut [Node<'a>] {
         &mut self.children
     }
@@ -261,12 +919,156 @@ impl<'a> Element<'a> {
     }
 }
 
+pub(crate) struct Fragment<'a> {
+    key: Option<&'a str>,
+    children: BumpVec<'a, Node<'a>>,
+}
+
+impl<'a> Fragment<'a> {
+    pub(crate) fn children(&self) -> &[Node<'a>] {
+        &self.children
+    }
+
+    pub(crate) fn children_mut(&mut self) -> &mut [Node<'a>] {
+        &mut self.children
+    }
+}
+
+pub(crate) struct Memo {
+    deps: Box<dyn Any>,
+    deps_eq: fn(&dyn Any, &dyn Any) -> bool,
+    render: Box<dyn Fn(&dyn Any) -> VDom>,
+    rendered: Option<VDom>,
+}
+
+impl Memo {
+    fn new<D, F>(deps: D, render: F, context: Namespace) -> Self
+    where
+        D: PartialEq + 'static,
+        F: Fn(&D, &mut VDom) + 'static,
+    {
+        Memo {
+            deps: Box::new(deps),
+            deps_eq: |a, b| match (a.downcast_ref::<D>(), b.downcast_ref::<D>()) {
+                (Some(a), Some(b)) => a == b,
+                _ => false,
+            },
+            render: Box::new(move |deps| {
+                let mut vdom = VDom::with_context(context);
+
+                render(deps.downcast_ref().unwrap(), &mut vdom);
+
+                vdom
+            }),
+            rendered: None,
+        }
+    }
+
+    /// Whether the deps of `self` and `other` are of the same type and compare equal.
+    pub(crate) fn deps_eq(&self, other: &Memo) -> bool {
+        (self.deps_eq)(&*self.deps, &*other.deps)
+    }
+
+    /// Carries over the rendered nodes of the `old` memo, rather than rendering this memo.
+    pub(crate) fn reuse(&mut self, old: &mut Memo) {
+        self.rendered = old.rendered.take();
+    }
+
+    /// Returns the rendered nodes of this memo, rendering them if this memo has not been rendered
+    /// yet.
+    pub(crate) fn rendered_mut(&mut self) -> &mut VDom {
+        let Memo {
+            deps,
+            render,
+            rendered,
+            ..
+        } = self;
+
+        rendered.get_or_insert_with(|| {
+            let mut vdom = render(&**deps);
+
+            forward_on_rendered(&mut vdom);
+
+            vdom
+        })
+    }
+
+    /// Returns the rendered nodes of this memo, if it has been rendered.
+    pub(crate) fn rendered(&self) -> Option<&VDom> {
+        self.rendered.as_ref()
+    }
+
+    /// Renders the nodes of this memo into a new virtual DOM, without storing the result.
+    pub(crate) fn render(&self) -> VDom {
+        (self.render)(&*self.deps)
+    }
+}
+
+pub(crate) struct ComponentNode<'a> {
+    key: Option<&'a str>,
+    instance: Box<dyn AnyInstance>,
+}
+
+impl<'a> ComponentNode<'a> {
+    fn new<C>(key: Option<&'a str>, component: C, props: C::Props, context: Namespace) -> Self
+    where
+        C: Component,
+    {
+        ComponentNode {
+            key,
+            instance: Box::new(Instance::new(component, props, context)),
+        }
+    }
+
+    pub(crate) fn instance(&self) -> &dyn AnyInstance {
+        &*self.instance
+    }
+
+    pub(crate) fn instance_mut(&mut self) -> &mut dyn AnyInstance {
+        &mut *self.instance
+    }
+}
+
+pub(crate) struct Props {
+    value: Rc<dyn Any>,
+    eq: fn(&dyn Any, &dyn Any) -> bool,
+}
+
+impl Props {
+    fn new<P>(value: P) -> Self
+    where
+        P: PartialEq + 'static,
+    {
+        Props {
+            value: Rc::new(value),
+            eq: |a, b| match (a.downcast_ref::<P>(), b.downcast_ref::<P>()) {
+                (Some(a), Some(b)) => a == b,
+                _ => false,
+            },
+        }
+    }
+
+    pub(crate) fn value(&self) -> Rc<dyn Any> {
+        self.value.clone()
+    }
+
+    /// Whether the props of `self` and `other` are of the same type and compare equal.
+    pub(crate) fn eq(&self, other: &Props) -> bool {
+        (self.eq)(&*self.value, &*other.value)
+    }
+}
+
 pub(crate) struct Attribute<'a> {
+    namespace: Option<Namespace>,
     name: &'a Name,
     value: &'a str,
 }
 
 impl<'a> Attribute<'a> {
+    pub(crate) fn namespace(&self) -> Option<Namespace> {
+        self.namespace
+    }
+
     pub(crate) fn name(&self) -> &Name {
         &self.name
     }
@@ -275,3 +1077,18 @@ impl<'a> Attribute<'a> {
         &self.value
     }
 }
+
+pub(crate) struct Property<'a> {
+    name: &'a str,
+    value: PropertyValue<'a>,
+}
+
+impl<'a> Property<'a> {
+    pub(crate) fn name(&self) -> &str {
+        self.name
+    }
+
+    pub(crate) fn value(&self) -> PropertyValue {
+        self.value
+    }
+}
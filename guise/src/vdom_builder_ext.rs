@@ -6,7 +6,9 @@ use arwa::html::*;
 use arwa::ui::*;
 use futures::Sink;
 
-use crate::vdom::ElementBuilder;
+use crate::listener::ListenerOptions;
+use crate::svg::*;
+use crate::vdom::{ElementBuilder, Namespace, PropertyValue};
 
 macro_rules! known_element_fn {
     ($fn_name:ident, $element:ident) => {
@@ -134,15 +136,72 @@ pub trait ChildKnownElementExt: child_known_element_ext_seal::Seal {
     known_element_fn!(child_template, HtmlTemplateElement);
 }
 
+macro_rules! svg_element_fn {
+    ($fn_name:ident, $element:ident) => {
+        fn $fn_name<F>(&mut self, f: F)
+        where
+            F: FnOnce(ElementBuilder<$element>),
+        {
+            ChildSvgElementExt::child_svg_element::<$element, F>(self, f)
+        }
+    };
+}
+
+pub(crate) mod child_svg_element_ext_seal {
+    pub trait Seal {}
+}
+
+pub trait ChildSvgElementExt: child_svg_element_ext_seal::Seal {
+    fn child_svg_element<T, F>(&mut self, f: F)
+    where
+        T: KnownSvgElement,
+        F: FnOnce(ElementBuilder<T>);
+
+    svg_element_fn!(child_svg, SvgSvgElement);
+    svg_element_fn!(child_svg_g, SvgGElement);
+    svg_element_fn!(child_svg_defs, SvgDefsElement);
+    svg_element_fn!(child_svg_symbol, SvgSymbolElement);
+    svg_element_fn!(child_svg_use, SvgUseElement);
+    svg_element_fn!(child_svg_title, SvgTitleElement);
+    svg_element_fn!(child_svg_desc, SvgDescElement);
+    svg_element_fn!(child_svg_path, SvgPathElement);
+    svg_element_fn!(child_svg_circle, SvgCircleElement);
+    svg_element_fn!(child_svg_ellipse, SvgEllipseElement);
+    svg_element_fn!(child_svg_line, SvgLineElement);
+    svg_element_fn!(child_svg_polygon, SvgPolygonElement);
+    svg_element_fn!(child_svg_polyline, SvgPolylineElement);
+    svg_element_fn!(child_svg_rect, SvgRectElement);
+    svg_element_fn!(child_svg_text, SvgTextElement);
+    svg_element_fn!(child_svg_tspan, SvgTspanElement);
+    svg_element_fn!(child_svg_image, SvgImageElement);
+    svg_element_fn!(child_svg_foreign_object, SvgForeignObjectElement);
+    svg_element_fn!(child_svg_linear_gradient, SvgLinearGradientElement);
+    svg_element_fn!(child_svg_radial_gradient, SvgRadialGradientElement);
+    svg_element_fn!(child_svg_stop, SvgStopElement);
+    svg_element_fn!(child_svg_clip_path, SvgClipPathElement);
+    svg_element_fn!(child_svg_mask, SvgMaskElement);
+    svg_element_fn!(child_svg_pattern, SvgPatternElement);
+    svg_element_fn!(child_svg_marker, SvgMarkerElement);
+}
+
 macro_rules! ui_event_sink_fn {
-    ($fn_name:ident, $event:ident) => {
+    ($fn_name:ident, $fn_name_with_options:ident, $event:ident) => {
         fn $fn_name<S>(&mut self, sink: S)
         where
             E: EventTarget + 'static,
             S: Sink<$event<E>> + 'static,
             S::Error: Debug,
         {
-            sink_ui_event_ext_seal::Seal::sink_event(self, sink);
+            sink_ui_event_ext_seal::Seal::sink_event(self, sink, ListenerOptions::default());
+        }
+
+        fn $fn_name_with_options<S>(&mut self, sink: S, options: ListenerOptions)
+        where
+            E: EventTarget + 'static,
+            S: Sink<$event<E>> + 'static,
+            S::Error: Debug,
+        {
+            sink_ui_event_ext_seal::Seal::sink_event(self, sink, options);
         }
     };
 }
@@ -152,8 +211,10 @@ pub(crate) mod sink_ui_event_ext_seal {
     use futures::Sink;
     use std::fmt::Debug;
 
+    use crate::listener::ListenerOptions;
+
     pub trait Seal<E> {
-        fn sink_event<T, S>(&mut self, sink: S)
+        fn sink_event<T, S>(&mut self, sink: S, options: ListenerOptions)
         where
             E: EventTarget,
             T: TypedEvent<CurrentTarget = E> + 'static,
@@ -163,34 +224,94 @@ pub(crate) mod sink_ui_event_ext_seal {
 }
 
 pub trait SinkUIEventExt<E>: sink_ui_event_ext_seal::Seal<E> {
-    ui_event_sink_fn!(sink_input, InputEvent);
-    ui_event_sink_fn!(sink_before_input, BeforeInputEvent);
-    ui_event_sink_fn!(sink_focus_in, FocusInEvent);
-    ui_event_sink_fn!(sink_focus_out, FocusOutEvent);
-    ui_event_sink_fn!(sink_click, ClickEvent);
-    ui_event_sink_fn!(sink_dbl_click, DblClickEvent);
-    ui_event_sink_fn!(sink_aux_click, AuxClickEvent);
-    ui_event_sink_fn!(sink_context_menu, ContextMenuEvent);
-    ui_event_sink_fn!(sink_pointer_cancel, PointerCancelEvent);
-    ui_event_sink_fn!(sink_pointer_down, PointerDownEvent);
-    ui_event_sink_fn!(sink_pointer_move, PointerMoveEvent);
-    ui_event_sink_fn!(sink_pointer_up, PointerUpEvent);
-    ui_event_sink_fn!(sink_pointer_out, PointerOutEvent);
-    ui_event_sink_fn!(sink_pointer_over, PointerOverEvent);
-    ui_event_sink_fn!(sink_pointer_enter, PointerEnterEvent);
-    ui_event_sink_fn!(sink_pointer_leave, PointerLeaveEvent);
-    ui_event_sink_fn!(sink_got_pointer_capture, GotPointerCaptureEvent);
-    ui_event_sink_fn!(sink_lost_pointer_capture, LostPointerCaptureEvent);
-    ui_event_sink_fn!(sink_drag, DragEvent);
-    ui_event_sink_fn!(sink_drag_end, DragEndEvent);
-    ui_event_sink_fn!(sink_drag_enter, DragEnterEvent);
-    ui_event_sink_fn!(sink_drag_leave, DragLeaveEvent);
-    ui_event_sink_fn!(sink_drag_over, DragOverEvent);
-    ui_event_sink_fn!(sink_drag_start, DragStartEvent);
-    ui_event_sink_fn!(sink_drop, DropEvent);
-    ui_event_sink_fn!(sink_key_down, KeyDownEvent);
-    ui_event_sink_fn!(sink_key_up, KeyUpEvent);
-    ui_event_sink_fn!(sink_wheel, WheelEvent);
+    ui_event_sink_fn!(sink_input, sink_input_with_options, InputEvent);
+    ui_event_sink_fn!(
+        sink_before_input,
+        sink_before_input_with_options,
+        BeforeInputEvent
+    );
+    ui_event_sink_fn!(sink_focus_in, sink_focus_in_with_options, FocusInEvent);
+    ui_event_sink_fn!(sink_focus_out, sink_focus_out_with_options, FocusOutEvent);
+    ui_event_sink_fn!(sink_click, sink_click_with_options, ClickEvent);
+    ui_event_sink_fn!(sink_dbl_click, sink_dbl_click_with_options, DblClickEvent);
+    ui_event_sink_fn!(sink_aux_click, sink_aux_click_with_options, AuxClickEvent);
+    ui_event_sink_fn!(
+        sink_context_menu,
+        sink_context_menu_with_options,
+        ContextMenuEvent
+    );
+    ui_event_sink_fn!(
+        sink_pointer_cancel,
+        sink_pointer_cancel_with_options,
+        PointerCancelEvent
+    );
+    ui_event_sink_fn!(
+        sink_pointer_down,
+        sink_pointer_down_with_options,
+        PointerDownEvent
+    );
+    ui_event_sink_fn!(
+        sink_pointer_move,
+        sink_pointer_move_with_options,
+        PointerMoveEvent
+    );
+    ui_event_sink_fn!(
+        sink_pointer_up,
+        sink_pointer_up_with_options,
+        PointerUpEvent
+    );
+    ui_event_sink_fn!(
+        sink_pointer_out,
+        sink_pointer_out_with_options,
+        PointerOutEvent
+    );
+    ui_event_sink_fn!(
+        sink_pointer_over,
+        sink_pointer_over_with_options,
+        PointerOverEvent
+    );
+    ui_event_sink_fn!(
+        sink_pointer_enter,
+        sink_pointer_enter_with_options,
+        PointerEnterEvent
+    );
+    ui_event_sink_fn!(
+        sink_pointer_leave,
+        sink_pointer_leave_with_options,
+        PointerLeaveEvent
+    );
+    ui_event_sink_fn!(
+        sink_got_pointer_capture,
+        sink_got_pointer_capture_with_options,
+        GotPointerCaptureEvent
+    );
+    ui_event_sink_fn!(
+        sink_lost_pointer_capture,
+        sink_lost_pointer_capture_with_options,
+        LostPointerCaptureEvent
+    );
+    ui_event_sink_fn!(sink_drag, sink_drag_with_options, DragEvent);
+    ui_event_sink_fn!(sink_drag_end, sink_drag_end_with_options, DragEndEvent);
+    ui_event_sink_fn!(
+        sink_drag_enter,
+        sink_drag_enter_with_options,
+        DragEnterEvent
+    );
+    ui_event_sink_fn!(
+        sink_drag_leave,
+        sink_drag_leave_with_options,
+        DragLeaveEvent
+    );
+    ui_event_sink_fn!(sink_drag_over, sink_drag_over_with_options, DragOverEvent);
+    ui_event_sink_fn!(
+        sink_drag_start,
+        sink_drag_start_with_options,
+        DragStartEvent
+    );
+    ui_event_sink_fn!(sink_drop, sink_drop_with_options, DropEvent);
+    ui_event_sink_fn!(sink_key_down, sink_key_down_with_options, KeyDownEvent);
+    ui_event_sink_fn!(sink_key_up, sink_key_up_with_options, KeyUpEvent);
+    ui_event_sink_fn!(sink_wheel, sink_wheel_with_options, WheelEvent);
 }
 
 macro_rules! attr_fn {
@@ -216,15 +337,34 @@ macro_rules! boolean_attr_fn {
     };
 }
 
+macro_rules! ns_attr_fn {
+    ($fn_name:ident, $namespace:ident, $attr_name:literal) => {
+        fn $fn_name(&mut self, value: &str) {
+            self.attr_ns(
+                Namespace::$namespace,
+                arwa::dom::Name::from_statically_parsed(arwa::dom::StaticallyParsedName {
+                    name: $attr_name,
+                }),
+                value,
+            );
+        }
+    };
+}
+
 macro_rules! attr_ext_seal {
     ($seal_mod:ident) => {
         pub(crate) mod $seal_mod {
             use arwa::dom::Name;
 
+            use crate::vdom::Namespace;
+
             pub trait Seal {
                 #[doc(hidden)]
                 fn attr(&mut self, name: Name, value: &str);
 
+                #[doc(hidden)]
+                fn attr_ns(&mut self, namespace: Namespace, name: Name, value: &str);
+
                 #[doc(hidden)]
                 fn boolean_attr(&mut self, name: Name);
             }
@@ -259,6 +399,10 @@ impl<'a, 'b, E> global_attr_ext_seal::Seal for ElementBuilder<'a, 'b, E> {
         ElementBuilder::attr(self, name, value);
     }
 
+    fn attr_ns(&mut self, namespace: Namespace, name: Name, value: &str) {
+        ElementBuilder::attr_ns(self, namespace, name, value);
+    }
+
     fn boolean_attr(&mut self, name: Name) {
         ElementBuilder::boolean_attr(self, name)
     }
@@ -273,6 +417,10 @@ macro_rules! impl_attr_ext {
                 ElementBuilder::attr(self, name, value)
             }
 
+            fn attr_ns(&mut self, namespace: Namespace, name: Name, value: &str) {
+                ElementBuilder::attr_ns(self, namespace, name, value)
+            }
+
             fn boolean_attr(&mut self, name: Name) {
                 ElementBuilder::boolean_attr(self, name)
             }
@@ -820,3 +968,469 @@ pub trait DataAttrExt: data_attr_ext_seal::Seal {
 }
 
 impl_attr_ext!(data_attr_ext_seal, DataAttrExt, HtmlDataElement);
+
+attr_ext_seal!(svg_presentation_attr_ext_seal);
+
+/// Attributes that are available on all SVG elements.
+pub trait SvgPresentationAttrExt: svg_presentation_attr_ext_seal::Seal {
+    attr_fn!(attr_clip_path, "clip-path");
+    attr_fn!(attr_clip_rule, "clip-rule");
+    attr_fn!(attr_color, "color");
+    attr_fn!(attr_display, "display");
+    attr_fn!(attr_fill, "fill");
+    attr_fn!(attr_fill_opacity, "fill-opacity");
+    attr_fn!(attr_fill_rule, "fill-rule");
+    attr_fn!(attr_filter, "filter");
+    attr_fn!(attr_font_family, "font-family");
+    attr_fn!(attr_font_size, "font-size");
+    attr_fn!(attr_font_weight, "font-weight");
+    attr_fn!(attr_mask, "mask");
+    attr_fn!(attr_opacity, "opacity");
+    attr_fn!(attr_stroke, "stroke");
+    attr_fn!(attr_stroke_dasharray, "stroke-dasharray");
+    attr_fn!(attr_stroke_dashoffset, "stroke-dashoffset");
+    attr_fn!(attr_stroke_linecap, "stroke-linecap");
+    attr_fn!(attr_stroke_linejoin, "stroke-linejoin");
+    attr_fn!(attr_stroke_opacity, "stroke-opacity");
+    attr_fn!(attr_stroke_width, "stroke-width");
+    attr_fn!(attr_transform, "transform");
+    attr_fn!(attr_visibility, "visibility");
+}
+
+impl<'a, 'b, E> svg_presentation_attr_ext_seal::Seal for ElementBuilder<'a, 'b, E>
+where
+    E: KnownSvgElement,
+{
+    fn attr(&mut self, name: Name, value: &str) {
+        ElementBuilder::attr(self, name, value)
+    }
+
+    fn attr_ns(&mut self, namespace: Namespace, name: Name, value: &str) {
+        ElementBuilder::attr_ns(self, namespace, name, value)
+    }
+
+    fn boolean_attr(&mut self, name: Name) {
+        ElementBuilder::boolean_attr(self, name)
+    }
+}
+
+impl<'a, 'b, E> SvgPresentationAttrExt for ElementBuilder<'a, 'b, E> where E: KnownSvgElement {}
+
+attr_ext_seal!(svg_svg_attr_ext_seal);
+
+pub trait SvgSvgAttrExt: svg_svg_attr_ext_seal::Seal {
+    attr_fn!(attr_height, "height");
+    attr_fn!(attr_preserve_aspect_ratio, "preserveAspectRatio");
+    attr_fn!(attr_view_box, "viewBox");
+    attr_fn!(attr_width, "width");
+    attr_fn!(attr_x, "x");
+    attr_fn!(attr_y, "y");
+}
+
+impl_attr_ext!(svg_svg_attr_ext_seal, SvgSvgAttrExt, SvgSvgElement);
+
+attr_ext_seal!(svg_symbol_attr_ext_seal);
+
+pub trait SvgSymbolAttrExt: svg_symbol_attr_ext_seal::Seal {
+    attr_fn!(attr_preserve_aspect_ratio, "preserveAspectRatio");
+    attr_fn!(attr_view_box, "viewBox");
+}
+
+impl_attr_ext!(svg_symbol_attr_ext_seal, SvgSymbolAttrExt, SvgSymbolElement);
+
+attr_ext_seal!(svg_use_attr_ext_seal);
+
+pub trait SvgUseAttrExt: svg_use_attr_ext_seal::Seal {
+    attr_fn!(attr_height, "height");
+    attr_fn!(attr_href, "href");
+    attr_fn!(attr_width, "width");
+    attr_fn!(attr_x, "x");
+    ns_attr_fn!(attr_xlink_href, XLink, "xlink:href");
+    attr_fn!(attr_y, "y");
+}
+
+impl_attr_ext!(svg_use_attr_ext_seal, SvgUseAttrExt, SvgUseElement);
+
+attr_ext_seal!(svg_path_attr_ext_seal);
+
+pub trait SvgPathAttrExt: svg_path_attr_ext_seal::Seal {
+    attr_fn!(attr_d, "d");
+    attr_fn!(attr_path_length, "pathLength");
+}
+
+impl_attr_ext!(svg_path_attr_ext_seal, SvgPathAttrExt, SvgPathElement);
+
+attr_ext_seal!(svg_circle_attr_ext_seal);
+
+pub trait SvgCircleAttrExt: svg_circle_attr_ext_seal::Seal {
+    attr_fn!(attr_cx, "cx");
+    attr_fn!(attr_cy, "cy");
+    attr_fn!(attr_r, "r");
+}
+
+impl_attr_ext!(svg_circle_attr_ext_seal, SvgCircleAttrExt, SvgCircleElement);
+
+attr_ext_seal!(svg_ellipse_attr_ext_seal);
+
+pub trait SvgEllipseAttrExt: svg_ellipse_attr_ext_seal::Seal {
+    attr_fn!(attr_cx, "cx");
+    attr_fn!(attr_cy, "cy");
+    attr_fn!(attr_rx, "rx");
+    attr_fn!(attr_ry, "ry");
+}
+
+impl_attr_ext!(
+    svg_ellipse_attr_ext_seal,
+    SvgEllipseAttrExt,
+    SvgEllipseElement
+);
+
+attr_ext_seal!(svg_line_attr_ext_seal);
+
+pub trait SvgLineAttrExt: svg_line_attr_ext_seal::Seal {
+    attr_fn!(attr_x1, "x1");
+    attr_fn!(attr_x2, "x2");
+    attr_fn!(attr_y1, "y1");
+    attr_fn!(attr_y2, "y2");
+}
+
+impl_attr_ext!(svg_line_attr_ext_seal, SvgLineAttrExt, SvgLineElement);
+
+attr_ext_seal!(svg_polygon_attr_ext_seal);
+
+pub trait SvgPolygonAttrExt: svg_polygon_attr_ext_seal::Seal {
+    attr_fn!(attr_points, "points");
+}
+
+impl_attr_ext!(
+    svg_polygon_attr_ext_seal,
+    SvgPolygonAttrExt,
+    SvgPolygonElement
+);
+impl_attr_ext!(
+    svg_polygon_attr_ext_seal,
+    SvgPolygonAttrExt,
+    SvgPolylineElement
+);
+
+attr_ext_seal!(svg_rect_attr_ext_seal);
+
+pub trait SvgRectAttrExt: svg_rect_attr_ext_seal::Seal {
+    attr_fn!(attr_height, "height");
+    attr_fn!(attr_rx, "rx");
+    attr_fn!(attr_ry, "ry");
+    attr_fn!(attr_width, "width");
+    attr_fn!(attr_x, "x");
+    attr_fn!(attr_y, "y");
+}
+
+impl_attr_ext!(svg_rect_attr_ext_seal, SvgRectAttrExt, SvgRectElement);
+
+attr_ext_seal!(svg_text_attr_ext_seal);
+
+pub trait SvgTextAttrExt: svg_text_attr_ext_seal::Seal {
+    attr_fn!(attr_dx, "dx");
+    attr_fn!(attr_dy, "dy");
+    attr_fn!(attr_text_anchor, "text-anchor");
+    attr_fn!(attr_x, "x");
+    attr_fn!(attr_y, "y");
+}
+
+impl_attr_ext!(svg_text_attr_ext_seal, SvgTextAttrExt, SvgTextElement);
+impl_attr_ext!(svg_text_attr_ext_seal, SvgTextAttrExt, SvgTspanElement);
+
+attr_ext_seal!(svg_image_attr_ext_seal);
+
+pub trait SvgImageAttrExt: svg_image_attr_ext_seal::Seal {
+    attr_fn!(attr_height, "height");
+    attr_fn!(attr_href, "href");
+    attr_fn!(attr_preserve_aspect_ratio, "preserveAspectRatio");
+    attr_fn!(attr_width, "width");
+    attr_fn!(attr_x, "x");
+    ns_attr_fn!(attr_xlink_href, XLink, "xlink:href");
+    attr_fn!(attr_y, "y");
+}
+
+impl_attr_ext!(svg_image_attr_ext_seal, SvgImageAttrExt, SvgImageElement);
+
+attr_ext_seal!(svg_foreign_object_attr_ext_seal);
+
+pub trait SvgForeignObjectAttrExt: svg_foreign_object_attr_ext_seal::Seal {
+    attr_fn!(attr_height, "height");
+    attr_fn!(attr_width, "width");
+    attr_fn!(attr_x, "x");
+    attr_fn!(attr_y, "y");
+}
+
+impl_attr_ext!(
+    svg_foreign_object_attr_ext_seal,
+    SvgForeignObjectAttrExt,
+    SvgForeignObjectElement
+);
+
+attr_ext_seal!(svg_linear_gradient_attr_ext_seal);
+
+pub trait SvgLinearGradientAttrExt: svg_linear_gradient_attr_ext_seal::Seal {
+    attr_fn!(attr_gradient_transform, "gradientTransform");
+    attr_fn!(attr_gradient_units, "gradientUnits");
+    attr_fn!(attr_href, "href");
+    attr_fn!(attr_spread_method, "spreadMethod");
+    attr_fn!(attr_x1, "x1");
+    attr_fn!(attr_x2, "x2");
+    attr_fn!(attr_y1, "y1");
+    attr_fn!(attr_y2, "y2");
+}
+
+impl_attr_ext!(
+    svg_linear_gradient_attr_ext_seal,
+    SvgLinearGradientAttrExt,
+    SvgLinearGradientElement
+);
+
+attr_ext_seal!(svg_radial_gradient_attr_ext_seal);
+
+pub trait SvgRadialGradientAttrExt: svg_radial_gradient_attr_ext_seal::Seal {
+    attr_fn!(attr_cx, "cx");
+    attr_fn!(attr_cy, "cy");
+    attr_fn!(attr_fr, "fr");
+    attr_fn!(attr_fx, "fx");
+    attr_fn!(attr_fy, "fy");
+    attr_fn!(attr_gradient_transform, "gradientTransform");
+    attr_fn!(attr_gradient_units, "gradientUnits");
+    attr_fn!(attr_href, "href");
+    attr_fn!(attr_r, "r");
+    attr_fn!(attr_spread_method, "spreadMethod");
+}
+
+impl_attr_ext!(
+    svg_radial_gradient_attr_ext_seal,
+    SvgRadialGradientAttrExt,
+    SvgRadialGradientElement
+);
+
+attr_ext_seal!(svg_stop_attr_ext_seal);
+
+pub trait SvgStopAttrExt: svg_stop_attr_ext_seal::Seal {
+    attr_fn!(attr_offset, "offset");
+    attr_fn!(attr_stop_color, "stop-color");
+    attr_fn!(attr_stop_opacity, "stop-opacity");
+}
+
+impl_attr_ext!(svg_stop_attr_ext_seal, SvgStopAttrExt, SvgStopElement);
+
+attr_ext_seal!(svg_clip_path_attr_ext_seal);
+
+pub trait SvgClipPathAttrExt: svg_clip_path_attr_ext_seal::Seal {
+    attr_fn!(attr_clip_path_units, "clipPathUnits");
+}
+
+impl_attr_ext!(
+    svg_clip_path_attr_ext_seal,
+    SvgClipPathAttrExt,
+    SvgClipPathElement
+);
+
+attr_ext_seal!(svg_mask_attr_ext_seal);
+
+pub trait SvgMaskAttrExt: svg_mask_attr_ext_seal::Seal {
+    attr_fn!(attr_height, "height");
+    attr_fn!(attr_mask_content_units, "maskContentUnits");
+    attr_fn!(attr_mask_units, "maskUnits");
+    attr_fn!(attr_width, "width");
+    attr_fn!(attr_x, "x");
+    attr_fn!(attr_y, "y");
+}
+
+impl_attr_ext!(svg_mask_attr_ext_seal, SvgMaskAttrExt, SvgMaskElement);
+
+attr_ext_seal!(svg_pattern_attr_ext_seal);
+
+pub trait SvgPatternAttrExt: svg_pattern_attr_ext_seal::Seal {
+    attr_fn!(attr_height, "height");
+    attr_fn!(attr_href, "href");
+    attr_fn!(attr_pattern_content_units, "patternContentUnits");
+    attr_fn!(attr_pattern_transform, "patternTransform");
+    attr_fn!(attr_pattern_units, "patternUnits");
+    attr_fn!(attr_view_box, "viewBox");
+    attr_fn!(attr_width, "width");
+    attr_fn!(attr_x, "x");
+    attr_fn!(attr_y, "y");
+}
+
+impl_attr_ext!(
+    svg_pattern_attr_ext_seal,
+    SvgPatternAttrExt,
+    SvgPatternElement
+);
+
+attr_ext_seal!(svg_marker_attr_ext_seal);
+
+pub trait SvgMarkerAttrExt: svg_marker_attr_ext_seal::Seal {
+    attr_fn!(attr_marker_height, "markerHeight");
+    attr_fn!(attr_marker_units, "markerUnits");
+    attr_fn!(attr_marker_width, "markerWidth");
+    attr_fn!(attr_orient, "orient");
+    attr_fn!(attr_ref_x, "refX");
+    attr_fn!(attr_ref_y, "refY");
+    attr_fn!(attr_view_box, "viewBox");
+}
+
+impl_attr_ext!(svg_marker_attr_ext_seal, SvgMarkerAttrExt, SvgMarkerElement);
+
+macro_rules! prop_fn {
+    ($fn_name:ident, $prop_name:literal, $tpe:ty) => {
+        fn $fn_name(&mut self, value: $tpe) {
+            self.prop($prop_name, PropertyValue::from(value));
+        }
+    };
+}
+
+macro_rules! prop_ext_seal {
+    ($seal_mod:ident) => {
+        pub(crate) mod $seal_mod {
+            use crate::vdom::PropertyValue;
+
+            pub trait Seal {
+                #[doc(hidden)]
+                fn prop(&mut self, name: &str, value: PropertyValue);
+            }
+        }
+    };
+}
+
+macro_rules! impl_prop_ext {
+    ($seal_mod:ident, $ext:ident, $element_tpe:ident) => {
+        impl<'a, 'b> $seal_mod::Seal for ElementBuilder<'a, 'b, $element_tpe> {
+            fn prop(&mut self, name: &str, value: PropertyValue) {
+                ElementBuilder::prop(self, name, value)
+            }
+        }
+
+        impl<'a, 'b> $ext for ElementBuilder<'a, 'b, $element_tpe> {}
+    };
+}
+
+prop_ext_seal!(global_prop_ext_seal);
+
+pub trait GlobalPropExt: global_prop_ext_seal::Seal {
+    prop_fn!(prop_scroll_left, "scrollLeft", f64);
+    prop_fn!(prop_scroll_top, "scrollTop", f64);
+}
+
+impl<'a, 'b, E> global_prop_ext_seal::Seal for ElementBuilder<'a, 'b, E> {
+    fn prop(&mut self, name: &str, value: PropertyValue) {
+        ElementBuilder::prop(self, name, value)
+    }
+}
+
+impl<'a, 'b, E> GlobalPropExt for ElementBuilder<'a, 'b, E> {}
+
+prop_ext_seal!(input_prop_ext_seal);
+
+pub trait InputPropExt: input_prop_ext_seal::Seal {
+    prop_fn!(prop_checked, "checked", bool);
+    prop_fn!(prop_indeterminate, "indeterminate", bool);
+    prop_fn!(prop_selection_end, "selectionEnd", u32);
+    prop_fn!(prop_selection_start, "selectionStart", u32);
+    prop_fn!(prop_value, "value", &str);
+    prop_fn!(prop_value_as_number, "valueAsNumber", f64);
+}
+
+impl_prop_ext!(input_prop_ext_seal, InputPropExt, HtmlInputElement);
+
+prop_ext_seal!(textarea_prop_ext_seal);
+
+pub trait TextareaPropExt: textarea_prop_ext_seal::Seal {
+    prop_fn!(prop_selection_end, "selectionEnd", u32);
+    prop_fn!(prop_selection_start, "selectionStart", u32);
+    prop_fn!(prop_value, "value", &str);
+}
+
+impl_prop_ext!(textarea_prop_ext_seal, TextareaPropExt, HtmlTextareaElement);
+
+prop_ext_seal!(select_prop_ext_seal);
+
+pub trait SelectPropExt: select_prop_ext_seal::Seal {
+    prop_fn!(prop_selected_index, "selectedIndex", i32);
+    prop_fn!(prop_value, "value", &str);
+}
+
+impl_prop_ext!(select_prop_ext_seal, SelectPropExt, HtmlSelectElement);
+
+prop_ext_seal!(option_prop_ext_seal);
+
+pub trait OptionPropExt: option_prop_ext_seal::Seal {
+    prop_fn!(prop_selected, "selected", bool);
+}
+
+impl_prop_ext!(option_prop_ext_seal, OptionPropExt, HtmlOptionElement);
+
+prop_ext_seal!(details_prop_ext_seal);
+
+pub trait DetailsPropExt: details_prop_ext_seal::Seal {
+    prop_fn!(prop_open, "open", bool);
+}
+
+impl_prop_ext!(details_prop_ext_seal, DetailsPropExt, HtmlDetailsElement);
+
+prop_ext_seal!(media_prop_ext_seal);
+
+pub trait MediaPropExt: media_prop_ext_seal::Seal {
+    prop_fn!(prop_current_time, "currentTime", f64);
+    prop_fn!(prop_muted, "muted", bool);
+    prop_fn!(prop_playback_rate, "playbackRate", f64);
+    prop_fn!(prop_volume, "volume", f64);
+}
+
+impl_prop_ext!(media_prop_ext_seal, MediaPropExt, HtmlAudioElement);
+impl_prop_ext!(media_prop_ext_seal, MediaPropExt, HtmlVideoElement);
+
+/// Controlled form state for `<input>` elements.
+///
+/// A controlled value is authoritative: like any DOM property (see [InputPropExt]), it is compared
+/// with the live state of the element on every patch, so that the live state matches the rendered
+/// value even if the user changed it since the previous patch. If the rendered value equals the
+/// live value, the element is left untouched, which preserves the caret position.
+///
+/// Controlled values are not part of the output of [render_to_string](crate::render_to_string);
+/// to include them in pre-rendered markup, also set the `value` or `checked` attribute.
+pub trait ControlledInputExt: InputPropExt {
+    fn controlled_value(&mut self, value: &str) {
+        self.prop_value(value);
+    }
+
+    fn controlled_checked(&mut self, checked: bool) {
+        self.prop_checked(checked);
+    }
+}
+
+impl<'a, 'b> ControlledInputExt for ElementBuilder<'a, 'b, HtmlInputElement> {}
+
+/// Controlled form state for `<textarea>` elements.
+///
+/// See [ControlledInputExt] for details; to include the value in pre-rendered markup, also render
+/// it as the text content of the `<textarea>`.
+pub trait ControlledTextareaExt: TextareaPropExt {
+    fn controlled_value(&mut self, value: &str) {
+        self.prop_value(value);
+    }
+}
+
+impl<'a, 'b> ControlledTextareaExt for ElementBuilder<'a, 'b, HtmlTextareaElement> {}
+
+/// Controlled form state for `<select>` elements.
+///
+/// See [ControlledInputExt] for details. The value is set after the `<option>` children of the
+/// `<select>` element have been patched; to include it in pre-rendered markup, also set the
+/// `selected` attribute on the selected `<option>`.
+pub trait ControlledSelectExt: SelectPropExt {
+    fn controlled_value(&mut self, value: &str) {
+        self.prop_value(value);
+    }
+
+    fn controlled_selected_index(&mut self, index: i32) {
+        self.prop_selected_index(index);
+    }
+}
+
+impl<'a, 'b> ControlledSelectExt for ElementBuilder<'a, 'b, HtmlSelectElement> {}
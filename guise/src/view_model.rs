@@ -11,12 +11,55 @@ use crate::VDom;
 pub struct Gone;
 
 struct InnerState<T> {
-    value: T,
-    waker: Option<Waker>,
+    value: RefCell<T>,
+    /// Updates that were made with [Updater::update_deferred] while the value was borrowed for
+    /// rendering; these are applied once rendering completes.
+    deferred: RefCell<Vec<Box<dyn FnOnce(&mut T)>>>,
+    /// Whether the value was updated since it was last rendered.
+    dirty: Cell<bool>,
+    waker: RefCell<Option<Waker>>,
+    watcher: RefCell<Option<ComponentWatcher>>,
+}
+
+impl<T> InnerState<T> {
+    fn mark_dirty(&self) {
+        self.dirty.set(true);
+
+        if let Some(waker) = self.waker.borrow_mut().take() {
+            waker.wake();
+        }
+
+        if let Some(watcher) = self.watcher.borrow().as_ref() {
+            watcher.notify();
+        }
+    }
+
+    /// Calls `f` with the current value, marking the value as rendered.
+    fn render<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        self.dirty.set(false);
+
+        let result = f(&self.value.borrow());
+
+        // Updates made by `f` were deferred and have marked the state as dirty again.
+        let deferred = self.deferred.take();
+
+        if !deferred.is_empty() {
+            let mut value = self.value.borrow_mut();
+
+            for update in deferred {
+                update(&mut value);
+            }
+        }
+
+        result
+    }
 }
 
 struct State<T> {
-    inner: Rc<RefCell<InnerState<T>>>,
+    inner: Rc<InnerState<T>>,
     gone: Rc<Cell<bool>>,
 }
 
@@ -45,13 +88,21 @@ pub struct ViewModel<T> {
 
 impl<T> ViewModel<T> {
     pub fn new(initial: T) -> Self {
+        ViewModel::with_watcher(initial, None)
+    }
+
+    /// Creates a view model whose updates are also reported to the `watcher`.
+    pub(crate) fn with_watcher(initial: T, watcher: Option<ComponentWatcher>) -> Self {
         ViewModel {
             internal: ViewModelInternal {
                 state: State {
-                    inner: Rc::new(RefCell::new(InnerState {
-                        value: initial,
-                        waker: None,
-                    })),
+                    inner: Rc::new(InnerState {
+                        value: RefCell::new(initial),
+                        deferred: RefCell::new(Vec::new()),
+                        dirty: Cell::new(true),
+                        waker: RefCell::new(None),
+                        watcher: RefCell::new(watcher),
+                    }),
                     gone: Rc::new(Cell::new(false)),
                 },
             },
@@ -64,6 +115,28 @@ impl<T> ViewModel<T> {
         }
     }
 
+    /// Calls `f` with the current state, marking the state as rendered.
+    ///
+    /// Used for the state of a [Component](crate::Component), which is rendered by the render
+    /// loop of its host element rather than by a [Rendered] stream.
+    pub(crate) fn render_with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        self.internal.state.inner.render(f)
+    }
+
+    /// Reports future updates to the `watcher` instead of the watcher the view model was created
+    /// with, e.g. when the component that owns the view model is adopted by a new render loop.
+    pub(crate) fn set_watcher(&self, watcher: ComponentWatcher) {
+        self.internal.state.inner.watcher.replace(Some(watcher));
+    }
+
+    /// Whether the state was updated since it was last rendered with `render_with`.
+    pub(crate) fn is_dirty(&self) -> bool {
+        self.internal.state.inner.dirty.get()
+    }
+
     pub fn rendered<F>(self, f: F) -> Rendered<T, F>
     where
         F: FnMut(&T) -> VDom + Unpin,
@@ -80,6 +153,12 @@ pub struct Updater<T> {
 }
 
 impl<T> Updater<T> {
+    /// Updates the state with `f` and schedules a render.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the state is currently being rendered (e.g. when `update` is called from a render
+    /// function); use [update_deferred](Updater::update_deferred) there.
     pub fn update<F>(&self, f: F) -> Result<(), Gone>
     where
         F: FnOnce(&mut T),
@@ -88,14 +167,34 @@ impl<T> Updater<T> {
             return Err(Gone);
         }
 
-        let mut state = self.internal.inner.borrow_mut();
+        let inner = &self.internal.inner;
 
-        f(&mut state.value);
+        f(&mut inner.value.borrow_mut());
+        inner.mark_dirty();
 
-        if let Some(waker) = state.waker.take() {
-            waker.wake();
+        Ok(())
+    }
+
+    /// Like [update](Updater::update), but if the state is currently being rendered, the update is
+    /// applied once rendering completes, and the state is rendered again.
+    pub fn update_deferred<F>(&self, f: F) -> Result<(), Gone>
+    where
+        F: FnOnce(&mut T) + 'static,
+    {
+        if self.internal.gone.get() {
+            return Err(Gone);
         }
 
+        let inner = &self.internal.inner;
+
+        if let Ok(mut value) = inner.value.try_borrow_mut() {
+            f(&mut value);
+        } else {
+            inner.deferred.borrow_mut().push(Box::new(f));
+        }
+
+        inner.mark_dirty();
+
         Ok(())
     }
 }
@@ -121,16 +220,16 @@ where
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let this = self.get_mut();
-        let mut state = this.internal.state.inner.borrow_mut();
-
-        if state.waker.is_some() {
-            Poll::Pending
-        } else {
-            state.waker = Some(cx.waker().clone());
+        let inner = &this.internal.state.inner;
 
-            let vdom = (this.f)(&state.value);
+        if inner.dirty.get() {
+            let vdom = inner.render(&mut this.f);
 
             Poll::Ready(Some(vdom))
+        } else {
+            *inner.waker.borrow_mut() = Some(cx.waker().clone());
+
+            Poll::Pending
         }
     }
 }
@@ -140,3 +239,69 @@ impl<T, F> Drop for Rendered<T, F> {
         self.internal.state.gone.replace(true);
     }
 }
+
+thread_local! {
+    static CURRENT_WATCHER: RefCell<Option<ComponentWatcher>> = RefCell::new(None);
+}
+
+/// Records whether the state of any of the [Component](crate::Component)s rendered by a host
+/// element was updated.
+///
+/// The view model of a component is attached to the watcher that is current (see
+/// [ComponentWatcher::watch]) when the component is first rendered, and again when its state is
+/// carried over into a new render, so that the host's render loop can wait for updates without
+/// visiting its components.
+#[derive(Clone, Default)]
+pub(crate) struct ComponentWatcher {
+    inner: Rc<WatcherInner>,
+}
+
+#[derive(Default)]
+struct WatcherInner {
+    updated: Cell<bool>,
+    waker: RefCell<Option<Waker>>,
+}
+
+impl ComponentWatcher {
+    pub(crate) fn new() -> Self {
+        ComponentWatcher::default()
+    }
+
+    /// Returns the current watcher, if any.
+    pub(crate) fn current() -> Option<ComponentWatcher> {
+        CURRENT_WATCHER.with(|current| current.borrow().clone())
+    }
+
+    /// Calls `f` with this watcher as the current watcher.
+    pub(crate) fn watch<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        let outer = CURRENT_WATCHER.with(|current| current.replace(Some(self.clone())));
+        let result = f();
+
+        CURRENT_WATCHER.with(|current| current.replace(outer));
+
+        result
+    }
+
+    /// Resolves if the state of a watched component was updated since the last time this resolved;
+    /// otherwise, registers the waker of the `cx` to be woken on the next update.
+    pub(crate) fn poll_updated(&self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.inner.updated.replace(false) {
+            Poll::Ready(())
+        } else {
+            *self.inner.waker.borrow_mut() = Some(cx.waker().clone());
+
+            Poll::Pending
+        }
+    }
+
+    fn notify(&self) {
+        self.inner.updated.set(true);
+
+        if let Some(waker) = self.inner.waker.borrow_mut().take() {
+            waker.wake();
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use guise::{Attribute, AttributeParseError, AttributeValue};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, AttributeValue)]
+enum Variant {
+    Primary,
+    #[attribute_value = "alt"]
+    Secondary,
+    DangerZone,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, AttributeValue)]
+enum Size {
+    Small,
+    #[attribute_value(fallback)]
+    Medium,
+    Large,
+}
+
+#[test]
+fn parse() {
+    assert_eq!(Variant::parse(Some("primary")), Ok(Some(Variant::Primary)));
+    assert_eq!(Variant::parse(Some(" ALT ")), Ok(Some(Variant::Secondary)));
+    assert_eq!(
+        Variant::parse(Some("Danger-Zone")),
+        Ok(Some(Variant::DangerZone))
+    );
+    assert_eq!(Variant::parse(None), Ok(None));
+}
+
+#[test]
+fn round_trip() {
+    for variant in [Variant::Primary, Variant::Secondary, Variant::DangerZone] {
+        assert_eq!(
+            Variant::parse(Some(&variant.to_string())),
+            Ok(Some(variant))
+        );
+    }
+
+    assert_eq!(Variant::Secondary.to_string(), "alt");
+    assert_eq!(Variant::DangerZone.to_string(), "danger-zone");
+}
+
+#[test]
+fn unknown_value() {
+    assert_eq!(
+        Variant::parse(Some("secondary")),
+        Err(AttributeParseError::new(
+            "expected one of `primary`, `alt`, `danger-zone`"
+        ))
+    );
+}
+
+#[test]
+fn fallback() {
+    assert_eq!(Size::parse(Some("large")), Ok(Some(Size::Large)));
+    assert_eq!(Size::parse(Some("huge")), Ok(Some(Size::Medium)));
+    assert_eq!(Size::parse(None), Ok(Some(Size::Medium)));
+}
@@ -0,0 +1,89 @@
+//! Browser tests for hydrating declarative shadow roots; run with `wasm-pack test --headless
+//! --chrome guise`.
+
+#![cfg(target_arch = "wasm32")]
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use arwa::dom::{ShadowRootMode, ShadowRootOptions};
+use arwa::html::{custom_element_name, GenericExtendableElement};
+use arwa::window::window;
+use futures::channel::oneshot;
+use futures::stream;
+use guise::{name, HydrationMismatch, RegisterOptions, VDom};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen(inline_js = r#"
+export function mount(html) {
+    const container = document.createElement("div");
+
+    // Unlike `innerHTML`, `setHTMLUnsafe` attaches declarative shadow roots.
+    container.setHTMLUnsafe(html);
+    document.body.appendChild(container);
+}
+"#)]
+extern "C" {
+    fn mount(html: &str);
+}
+
+thread_local! {
+    static MISMATCHES: Cell<usize> = Cell::new(0);
+}
+
+fn count_mismatch(_: &HydrationMismatch) {
+    MISMATCHES.with(|mismatches| mismatches.set(mismatches.get() + 1));
+}
+
+fn view() -> VDom {
+    let mut vdom = VDom::new();
+
+    vdom.child(name!("p"), |mut p| p.text("pre-rendered"));
+
+    vdom
+}
+
+#[wasm_bindgen_test]
+async fn hydrates_closed_declarative_shadow_root() {
+    let shadow_root_options = ShadowRootOptions {
+        mode: ShadowRootMode::Closed,
+        ..Default::default()
+    };
+    let (sender, receiver) = oneshot::channel();
+    let sender = Rc::new(RefCell::new(Some(sender)));
+
+    guise::register_with_shadow_root_and_options::<GenericExtendableElement, (), _, _>(
+        &window().custom_elements(),
+        shadow_root_options,
+        &custom_element_name!("x-closed-declarative"),
+        RegisterOptions {
+            hydrate: true,
+            on_hydration_mismatch: count_mismatch,
+            ..Default::default()
+        },
+        move |_, _| {
+            let sender = sender.clone();
+            let mut vdom = view();
+
+            vdom.on_rendered(move |_| {
+                if let Some(sender) = sender.borrow_mut().take() {
+                    let _ = sender.send(());
+                }
+            });
+
+            stream::iter(Some(vdom))
+        },
+    );
+
+    mount(&format!(
+        "<x-closed-declarative>{}</x-closed-declarative>",
+        guise::render_to_string_with_shadow_root(&view(), shadow_root_options)
+    ));
+
+    receiver.await.unwrap();
+
+    assert_eq!(MISMATCHES.with(|mismatches| mismatches.get()), 0);
+}
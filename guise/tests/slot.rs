@@ -0,0 +1,68 @@
+//! Browser tests for slot assignments; run with `wasm-pack test --headless --chrome guise`.
+
+#![cfg(target_arch = "wasm32")]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use arwa::dom::ShadowRootOptions;
+use arwa::html::{custom_element_name, GenericExtendableElement};
+use arwa::window::window;
+use futures::channel::mpsc;
+use futures::{stream, StreamExt};
+use guise::{name, VDom};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen(inline_js = r#"
+export function mount(html) {
+    const container = document.createElement("div");
+
+    container.innerHTML = html;
+    document.body.appendChild(container);
+}
+"#)]
+extern "C" {
+    fn mount(html: &str);
+}
+
+#[wasm_bindgen_test]
+async fn fallback_content_is_not_content() {
+    let (sender, mut receiver) = mpsc::unbounded();
+    let sender = Rc::new(RefCell::new(sender));
+
+    guise::register_with_shadow_root::<GenericExtendableElement, (), _, _>(
+        &window().custom_elements(),
+        ShadowRootOptions::default(),
+        &custom_element_name!("x-slot-fallback"),
+        move |host, _| {
+            let sender = sender.clone();
+            let slot = host.slot("");
+            let mut vdom = VDom::new();
+
+            vdom.child(name!("slot"), |mut slot| {
+                slot.child(name!("p"), |mut p| p.text("fallback"));
+            });
+            vdom.on_rendered(move |_| {
+                let _ = sender.borrow().unbounded_send(slot);
+            });
+
+            stream::iter(Some(vdom))
+        },
+    );
+
+    mount(
+        "<x-slot-fallback></x-slot-fallback>\
+        <x-slot-fallback><span>assigned</span></x-slot-fallback>",
+    );
+
+    let empty = receiver.next().await.unwrap();
+    let assigned = receiver.next().await.unwrap();
+
+    assert!(!empty.has_content());
+    assert!(empty.assigned_nodes().is_empty());
+    assert!(assigned.has_content());
+    assert_eq!(assigned.assigned_nodes().len(), 1);
+}
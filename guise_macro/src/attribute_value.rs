@@ -0,0 +1,195 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, Ident, Lit, Meta, NestedMeta, Variant};
+
+use crate::case::to_kebab_case;
+use crate::error_log::ErrorLog;
+
+pub fn expand_derive_attribute_value(input: &DeriveInput) -> Result<TokenStream, String> {
+    if let Data::Enum(ref data) = input.data {
+        let enum_name = &input.ident;
+        let mod_path = quote!(guise);
+        let mut log = ErrorLog::new();
+
+        let mut variants: Vec<ValueVariant> = Vec::new();
+
+        'outer: for variant in data.variants.iter() {
+            let variant = ValueVariant::from_ast(variant, &mut log);
+
+            for v in variants.iter() {
+                if variant.value.eq_ignore_ascii_case(&v.value) {
+                    log.log_error(format!(
+                        "Variants `{}` and `{}` declare the same attribute value.",
+                        &v.ident, &variant.ident
+                    ));
+
+                    continue 'outer;
+                }
+            }
+
+            variants.push(variant);
+        }
+
+        let fallbacks: Vec<&ValueVariant> = variants.iter().filter(|v| v.fallback).collect();
+
+        if fallbacks.len() > 1 {
+            log.log_error(format!(
+                "Multiple fallback variants for `{}`; at most one variant may be marked \
+                #[attribute_value(fallback)].",
+                enum_name
+            ));
+        }
+
+        let matches = variants.iter().map(|variant| {
+            let ident = &variant.ident;
+            let value = &variant.value;
+
+            quote! {
+                if value.eq_ignore_ascii_case(#value) {
+                    return Ok(Some(#enum_name::#ident));
+                }
+            }
+        });
+
+        let (absent, invalid) = if let Some(fallback) = fallbacks.first() {
+            let ident = &fallback.ident;
+
+            (
+                quote!(Ok(Some(#enum_name::#ident))),
+                quote!(Ok(Some(#enum_name::#ident))),
+            )
+        } else {
+            let expected = variants
+                .iter()
+                .map(|v| format!("`{}`", v.value))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let message = format!("expected one of {}", expected);
+
+            (
+                quote!(Ok(None)),
+                quote!(Err(#mod_path::AttributeParseError::new(#message))),
+            )
+        };
+
+        let values = variants.iter().map(|variant| {
+            let ident = &variant.ident;
+            let value = &variant.value;
+
+            quote!(#enum_name::#ident => #value)
+        });
+
+        let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+        let impl_block = quote! {
+            #[automatically_derived]
+            impl #impl_generics #mod_path::Attribute for #enum_name #ty_generics #where_clause {
+                fn parse(
+                    value: Option<&str>
+                ) -> Result<Option<Self>, #mod_path::AttributeParseError> {
+                    let value = if let Some(value) = value {
+                        value.trim()
+                    } else {
+                        return #absent;
+                    };
+
+                    #(#matches)*
+
+                    #invalid
+                }
+            }
+
+            #[automatically_derived]
+            impl #impl_generics ::core::fmt::Display for #enum_name #ty_generics #where_clause {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    f.write_str(match self {
+                        #(#values,)*
+                    })
+                }
+            }
+        };
+
+        let generated = quote! {
+            #[allow(non_upper_case_globals, unused_attributes, unused_qualifications)]
+            const _: () = {
+                #impl_block
+            };
+        };
+
+        log.compile().map(|_| generated)
+    } else {
+        Err("`AttributeValue` can only be derived for an enum.".into())
+    }
+}
+
+struct ValueVariant {
+    ident: Ident,
+    value: String,
+    fallback: bool,
+}
+
+impl ValueVariant {
+    pub fn from_ast(ast: &Variant, log: &mut ErrorLog) -> Self {
+        let variant_name = ast.ident.to_string();
+
+        if !matches!(ast.fields, Fields::Unit) {
+            log.log_error(format!(
+                "Variant `{}` has fields; `AttributeValue` can only be derived for an enum \
+                without fields.",
+                variant_name
+            ));
+        }
+
+        let mut value = None;
+        let mut fallback = false;
+
+        for attr in ast
+            .attrs
+            .iter()
+            .filter(|a| a.path.is_ident("attribute_value"))
+        {
+            match attr.parse_meta() {
+                Ok(Meta::NameValue(meta)) => {
+                    if value.is_some() {
+                        log.log_error(format!(
+                            "Multiple #[attribute_value = \"...\"] attributes for variant `{}`.",
+                            variant_name
+                        ));
+                    }
+
+                    if let Lit::Str(lit) = &meta.lit {
+                        value = Some(lit.value());
+                    } else {
+                        log.log_error(format!(
+                            "Malformed #[attribute_value] attribute for variant `{}`; expected \
+                            the value to be a string literal.",
+                            variant_name
+                        ));
+                    }
+                }
+                Ok(Meta::List(meta))
+                    if meta.nested.len() == 1
+                        && matches!(
+                            meta.nested.first(),
+                            Some(NestedMeta::Meta(Meta::Path(path))) if path.is_ident("fallback")
+                        ) =>
+                {
+                    fallback = true;
+                }
+                _ => {
+                    log.log_error(format!(
+                        "Malformed #[attribute_value] attribute for variant `{}`; expected \
+                        #[attribute_value = \"...\"] or #[attribute_value(fallback)].",
+                        variant_name
+                    ));
+                }
+            }
+        }
+
+        ValueVariant {
+            ident: ast.ident.clone(),
+            value: value.unwrap_or_else(|| to_kebab_case(&variant_name)),
+            fallback,
+        }
+    }
+}
@@ -1,8 +1,14 @@
 use proc_macro2::{Span, TokenStream};
 use quote::{quote, quote_spanned, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
-use syn::{Attribute, Data, DeriveInput, Field, Ident, Lit, Meta};
+use syn::{
+    Attribute, Data, DeriveInput, Expr, Field, Fields, Ident, Index, Lit, LitStr, Meta, Path,
+    Token, Type, Visibility,
+};
 
+use crate::case::RenameRule;
 use crate::error_log::ErrorLog;
 
 pub fn expand_derive_attributes(input: &DeriveInput) -> Result<TokenStream, String> {
@@ -11,16 +17,17 @@ pub fn expand_derive_attributes(input: &DeriveInput) -> Result<TokenStream, Stri
         let mod_path = quote!(guise);
         let mut log = ErrorLog::new();
 
+        let options = ContainerOptions::from_ast(&input.attrs, &mut log);
         let mut fields: Vec<AttributeField> = Vec::new();
 
         'outer: for (i, field) in data.fields.iter().enumerate() {
-            let field = AttributeField::from_ast(field, i, &mut log);
+            let field = AttributeField::from_ast(field, i, &options, &mut log);
 
             for f in fields.iter() {
                 if field.attribute_name == f.attribute_name {
                     log.log_error(format!(
-                        "Fields `{}` and `{}` declare the same attribute name.",
-                        &f.name, &field.name
+                        "Fields `{}` and `{}` both map to attribute name `{}`.",
+                        &f.name, &field.name, &field.attribute_name
                     ));
 
                     continue 'outer;
@@ -31,39 +38,104 @@ pub fn expand_derive_attributes(input: &DeriveInput) -> Result<TokenStream, Stri
         }
 
         let observed = fields.iter().map(|field| {
-            let field_name = &field.name;
+            let attribute_name = &field.attribute_name;
             let span = field.span;
 
-            let attribute_name = if let Some(attribute_name) = field.attribute_name.as_ref() {
-                quote!(#attribute_name)
-            } else {
-                quote!(#field_name)
-            };
-
             quote_spanned!(span=> {
                 #mod_path::name!(#attribute_name)
             })
         });
 
         let patterns = fields.iter().map(|field| {
-            let field_name = &field.name;
-            let field_ident = field
-                .ident
-                .clone()
-                .map(|i| i.into_token_stream())
-                .unwrap_or(field.position.into_token_stream());
+            let attribute_name = &field.attribute_name;
+            let field_ident = field.member();
+            let ty = &field.ty;
             let span = field.span;
 
-            let attribute_name = if let Some(attribute_name) = field.attribute_name.as_ref() {
-                quote!(#attribute_name)
+            let parsed = if let Some(parse_with) = field.parse_with.as_ref() {
+                quote_spanned!(span=>
+                    match value.as_deref() {
+                        Some(value) => #parse_with(value)
+                            .map(Some)
+                            .map_err(#mod_path::AttributeParseError::new),
+                        None => Ok(None),
+                    }
+                )
+            } else {
+                quote_spanned!(span=>
+                    <#ty as #mod_path::Attribute>::parse(value.as_deref())
+                )
+            };
+
+            let default = if let Some(default) = field.default.as_ref() {
+                quote_spanned!(span=> #default)
             } else {
-                quote!(#field_name)
+                quote_spanned!(span=> ::core::default::Default::default())
             };
 
             quote_spanned!(span=>
-                #attribute_name => #mod_path::Attribute::update(&mut self.#field_ident, value)
+                #attribute_name => match #parsed {
+                    Ok(Some(parsed)) => {
+                        self.#field_ident = parsed;
+
+                        Ok(())
+                    }
+                    Ok(None) => {
+                        self.#field_ident = #default;
+
+                        Ok(())
+                    }
+                    Err(error) => {
+                        self.#field_ident = #default;
+
+                        Err(#mod_path::AttributeError::new(
+                            attribute_name,
+                            value.unwrap_or_default(),
+                            error,
+                        ))
+                    }
+                }
+            )
+        });
+
+        let diff_name = options.diff.clone().unwrap_or_else(|| {
+            Ident::new(
+                &format!("{}Diff", struct_name.to_string().trim_start_matches("r#")),
+                struct_name.span(),
             )
         });
+        let diff_fields = fields.iter().map(|field| {
+            let vis = &field.vis;
+
+            if let Some(ident) = field.ident.as_ref() {
+                quote!(#vis #ident: bool)
+            } else {
+                quote!(#vis bool)
+            }
+        });
+        let diff_body = match data.fields {
+            Fields::Named(_) => quote!({ #(#diff_fields,)* }),
+            Fields::Unnamed(_) => quote!((#(#diff_fields,)*);),
+            Fields::Unit => quote!(;),
+        };
+        let vis = &input.vis;
+        let doc = format!(
+            "Records which attributes of a [{}] changed; generated by `#[derive(Attributes)]`.",
+            struct_name
+        );
+
+        let diff_struct = quote! {
+            #[doc = #doc]
+            #[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+            #vis struct #diff_name #diff_body
+        };
+
+        let record_patterns = fields.iter().map(|field| {
+            let attribute_name = &field.attribute_name;
+            let field_ident = field.member();
+
+            quote_spanned!(field.span=> #attribute_name => diff.#field_ident = true)
+        });
 
         let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
 
@@ -74,16 +146,27 @@ pub fn expand_derive_attributes(input: &DeriveInput) -> Result<TokenStream, Stri
                     #(#observed,)*
                 ];
 
+                type Diff = #diff_name;
+
+                fn record_change(diff: &mut #diff_name, attribute_name: &#mod_path::Name) {
+                    let as_str: &str = attribute_name.as_ref();
+
+                    match as_str {
+                        #(#record_patterns,)*
+                        _ => ()
+                    }
+                }
+
                 fn update(
                     &mut self,
                     attribute_name: &#mod_path::Name,
                     value: Option<String>
-                ) {
+                ) -> Result<(), #mod_path::AttributeError> {
                     let as_str: &str = attribute_name.as_ref();
 
                     match as_str {
                         #(#patterns,)*
-                        _ => ()
+                        _ => Ok(())
                     }
                 }
             }
@@ -96,6 +179,8 @@ pub fn expand_derive_attributes(input: &DeriveInput) -> Result<TokenStream, Stri
         );
 
         let generated = quote! {
+            #diff_struct
+
             #[allow(non_upper_case_globals, unused_attributes, unused_qualifications)]
             const #dummy_const: () = {
                 #[allow(unknown_lints)]
@@ -114,14 +199,32 @@ pub fn expand_derive_attributes(input: &DeriveInput) -> Result<TokenStream, Stri
 
 struct AttributeField {
     ident: Option<Ident>,
+    vis: Visibility,
     position: usize,
     name: String,
-    attribute_name: Option<String>,
+    ty: Type,
+    attribute_name: String,
+    default: Option<Expr>,
+    parse_with: Option<Path>,
     span: Span,
 }
 
 impl AttributeField {
-    pub fn from_ast(ast: &Field, position: usize, log: &mut ErrorLog) -> Self {
+    /// The field as a member expression, e.g. `self.#member`.
+    fn member(&self) -> TokenStream {
+        if let Some(ident) = self.ident.as_ref() {
+            ident.into_token_stream()
+        } else {
+            Index::from(self.position).into_token_stream()
+        }
+    }
+
+    pub fn from_ast(
+        ast: &Field,
+        position: usize,
+        options: &ContainerOptions,
+        log: &mut ErrorLog,
+    ) -> Self {
         let field_name = ast
             .ident
             .clone()
@@ -146,32 +249,12 @@ impl AttributeField {
         if let Some(attr) = name_attributes.first() {
             match attr.parse_meta() {
                 Ok(Meta::NameValue(meta)) => {
-                    // if meta..nested.len() != 1 {
-                    //     log.log_error(format!(
-                    //         "Malformed #[attribute_name] attribute for field `{}`; expected a single \
-                    //         string.",
-                    //         field_name
-                    //     ));
-                    // } else {
-                    //     let nested = meta.nested.first().unwrap();
-                    //
-                    //     if let NestedMeta::Lit(Lit::Str(lit)) = nested {
-                    //         attribute_name = Some(lit.value());
-                    //     } else {
-                    //         log.log_error(format!(
-                    //             "Malformed #[attribute_name] attribute for field `{}`; expected \
-                    //             name to be a string integer literal.",
-                    //             field_name
-                    //         ));
-                    //     }
-                    // }
-
                     if let Lit::Str(lit) = &meta.lit {
                         attribute_name = Some(lit.value());
                     } else {
                         log.log_error(format!(
                             "Malformed #[attribute_name] attribute for field `{}`; expected \
-                                name to be a string integer literal.",
+                                name to be a string literal.",
                             field_name
                         ));
                     }
@@ -185,12 +268,214 @@ impl AttributeField {
             }
         }
 
+        let mut default = None;
+        let mut parse_with = None;
+
+        for attr in ast.attrs.iter().filter(|a| a.path.is_ident("attribute")) {
+            match attr.parse_args_with(Punctuated::<FieldOption, Token![,]>::parse_terminated) {
+                Ok(options) => {
+                    for option in options {
+                        match option {
+                            FieldOption::Default(expr) if default.is_none() => {
+                                default = Some(expr);
+                            }
+                            FieldOption::ParseWith(path) if parse_with.is_none() => {
+                                parse_with = Some(path);
+                            }
+                            _ => log.log_error(format!(
+                                "Duplicate #[attribute] option for field `{}`.",
+                                field_name
+                            )),
+                        }
+                    }
+                }
+                Err(err) => {
+                    log.log_error(format!(
+                        "Malformed #[attribute] attribute for field `{}`: {}.",
+                        field_name, err
+                    ));
+                }
+            }
+        }
+
+        // An explicit #[attribute_name] is used as is; otherwise the name is derived from the
+        // field name with the container's `rename_all` rule and `prefix`.
+        let attribute_name = attribute_name.unwrap_or_else(|| {
+            let name = field_name.trim_start_matches("r#");
+            let name = match options.rename_all {
+                Some(rule) => rule.apply(name),
+                None => name.to_string(),
+            };
+
+            format!("{}{}", options.prefix.as_deref().unwrap_or(""), name)
+        });
+
+        if let Err(err) = validate_attribute_name(&attribute_name) {
+            log.log_error(format!(
+                "Invalid attribute name `{}` for field `{}`: {}.",
+                attribute_name, field_name, err
+            ));
+        }
+
         AttributeField {
             ident: ast.ident.clone(),
+            vis: ast.vis.clone(),
             position,
             name: field_name,
+            ty: ast.ty.clone(),
             attribute_name,
+            default,
+            parse_with,
             span: ast.span(),
         }
     }
 }
+
+/// Checks that `name` is a valid HTML attribute name that the browser will report to
+/// `attributeChangedCallback` unchanged.
+fn validate_attribute_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("attribute names cannot be empty".into());
+    }
+
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            return Err(format!(
+                "attribute names must be lowercase, as HTML lowercases `{}`",
+                c
+            ));
+        }
+
+        if c.is_control() || matches!(c, ' ' | '"' | '\'' | '>' | '/' | '=') || is_nonchar(c) {
+            return Err(format!("attribute names cannot contain {:?}", c));
+        }
+    }
+
+    Ok(())
+}
+
+fn is_nonchar(c: char) -> bool {
+    let c = c as u32;
+
+    (0xFDD0..=0xFDEF).contains(&c) || c & 0xFFFE == 0xFFFE
+}
+
+/// The options of a `#[attributes(...)]` container attribute.
+#[derive(Default)]
+struct ContainerOptions {
+    rename_all: Option<RenameRule>,
+    prefix: Option<String>,
+    diff: Option<Ident>,
+}
+
+impl ContainerOptions {
+    fn from_ast(attrs: &[Attribute], log: &mut ErrorLog) -> Self {
+        let mut options = ContainerOptions::default();
+
+        for attr in attrs.iter().filter(|a| a.path.is_ident("attributes")) {
+            match attr.parse_args_with(Punctuated::<ContainerOption, Token![,]>::parse_terminated) {
+                Ok(parsed) => {
+                    for option in parsed {
+                        match option {
+                            ContainerOption::RenameAll(lit) if options.rename_all.is_none() => {
+                                if let Ok(rule) = lit.value().parse::<RenameRule>() {
+                                    options.rename_all = Some(rule);
+                                } else {
+                                    log.log_error(format!(
+                                        "Unknown `rename_all` rule `{}`; expected \
+                                        \"kebab-case\", \"snake_case\" or \"lowercase\".",
+                                        lit.value()
+                                    ));
+                                }
+                            }
+                            ContainerOption::Prefix(lit) if options.prefix.is_none() => {
+                                options.prefix = Some(lit.value());
+                            }
+                            ContainerOption::Diff(lit) if options.diff.is_none() => {
+                                match lit.parse::<Ident>() {
+                                    Ok(ident) => options.diff = Some(ident),
+                                    Err(_) => log.log_error(format!(
+                                        "Invalid `diff` struct name `{}`; expected an identifier.",
+                                        lit.value()
+                                    )),
+                                }
+                            }
+                            _ => log.log_error("Duplicate #[attributes] option."),
+                        }
+                    }
+                }
+                Err(err) => {
+                    log.log_error(format!("Malformed #[attributes] attribute: {}.", err));
+                }
+            }
+        }
+
+        options
+    }
+}
+
+/// An option in a `#[attributes(...)]` container attribute.
+enum ContainerOption {
+    /// `rename_all = "..."`: the rule by which attribute names are derived from field names.
+    RenameAll(LitStr),
+    /// `prefix = "..."`: a prefix for attribute names derived from field names (e.g. `data-`).
+    Prefix(LitStr),
+    /// `diff = "..."`: the name of the generated diff struct, instead of the struct name with a
+    /// `Diff` suffix.
+    Diff(LitStr),
+}
+
+impl Parse for ContainerOption {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name: Ident = input.parse()?;
+
+        input.parse::<Token![=]>()?;
+
+        if name == "rename_all" {
+            Ok(ContainerOption::RenameAll(input.parse()?))
+        } else if name == "prefix" {
+            Ok(ContainerOption::Prefix(input.parse()?))
+        } else if name == "diff" {
+            Ok(ContainerOption::Diff(input.parse()?))
+        } else {
+            Err(syn::Error::new(
+                name.span(),
+                format!(
+                    "unknown option `{}`; expected `rename_all`, `prefix` or `diff`",
+                    name
+                ),
+            ))
+        }
+    }
+}
+
+/// An option in a `#[attribute(...)]` field attribute.
+enum FieldOption {
+    /// `default = expr`: the value of the field when the attribute is absent or invalid.
+    Default(Expr),
+    /// `parse_with = path`: a function `fn(&str) -> Result<T, E>` (where `E: Display`) that is
+    /// used to parse the attribute instead of the field type's `Attribute` implementation.
+    ParseWith(Path),
+}
+
+impl Parse for FieldOption {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name: Ident = input.parse()?;
+
+        input.parse::<Token![=]>()?;
+
+        if name == "default" {
+            Ok(FieldOption::Default(input.parse()?))
+        } else if name == "parse_with" {
+            Ok(FieldOption::ParseWith(input.parse()?))
+        } else {
+            Err(syn::Error::new(
+                name.span(),
+                format!(
+                    "unknown option `{}`; expected `default` or `parse_with`",
+                    name
+                ),
+            ))
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::str::FromStr;
+
+/// Splits an identifier into its words, on underscores, dashes and lowercase-to-uppercase
+/// boundaries (e.g. `initial_count` and `InitialCount` both split into `initial` and `count`).
+fn words(name: &str) -> Vec<String> {
+    let name = name.trim_start_matches("r#");
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut previous_was_lower = false;
+
+    for c in name.chars() {
+        if c == '_' || c == '-' {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+
+            previous_was_lower = false;
+
+            continue;
+        }
+
+        if c.is_uppercase() && previous_was_lower {
+            words.push(std::mem::take(&mut current));
+        }
+
+        previous_was_lower = c.is_lowercase() || c.is_numeric();
+        current.extend(c.to_lowercase());
+    }
+
+    if !current.is_empty() {
+        words.push(current);
+    }
+
+    words
+}
+
+/// Converts an identifier to kebab-case, e.g. `initial_count` to `initial-count`.
+pub fn to_kebab_case(name: &str) -> String {
+    words(name).join("-")
+}
+
+/// A `rename_all` rule, which derives attribute names from field names.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RenameRule {
+    /// `initial_count` becomes `initial-count`.
+    Kebab,
+    /// `initial_count` stays `initial_count`.
+    Snake,
+    /// `initial_count` becomes `initialcount`.
+    Lower,
+}
+
+impl FromStr for RenameRule {
+    type Err = ();
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        match rule {
+            "kebab-case" => Ok(RenameRule::Kebab),
+            "snake_case" => Ok(RenameRule::Snake),
+            "lowercase" => Ok(RenameRule::Lower),
+            _ => Err(()),
+        }
+    }
+}
+
+impl RenameRule {
+    pub fn apply(&self, name: &str) -> String {
+        match self {
+            RenameRule::Kebab => to_kebab_case(name),
+            RenameRule::Snake => words(name).join("_"),
+            RenameRule::Lower => words(name).concat(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kebab_case() {
+        assert_eq!(RenameRule::Kebab.apply("initial_count"), "initial-count");
+        assert_eq!(RenameRule::Kebab.apply("InitialCount"), "initial-count");
+        assert_eq!(RenameRule::Kebab.apply("r#type"), "type");
+        assert_eq!(RenameRule::Kebab.apply("item2_id"), "item2-id");
+    }
+
+    #[test]
+    fn snake_case() {
+        assert_eq!(RenameRule::Snake.apply("initial_count"), "initial_count");
+        assert_eq!(RenameRule::Snake.apply("InitialCount"), "initial_count");
+        assert_eq!(RenameRule::Snake.apply("_private__field"), "private_field");
+    }
+
+    #[test]
+    fn lowercase() {
+        assert_eq!(RenameRule::Lower.apply("initial_count"), "initialcount");
+        assert_eq!(RenameRule::Lower.apply("InitialCount"), "initialcount");
+    }
+
+    #[test]
+    fn rename_rule_from_str() {
+        assert_eq!("kebab-case".parse(), Ok(RenameRule::Kebab));
+        assert_eq!("snake_case".parse(), Ok(RenameRule::Snake));
+        assert_eq!("lowercase".parse(), Ok(RenameRule::Lower));
+        assert_eq!("camelCase".parse::<RenameRule>(), Err(()));
+    }
+}
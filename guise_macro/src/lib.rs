@@ -1,12 +1,25 @@
+mod attribute_value;
 mod attributes;
+mod case;
 mod error_log;
+mod view;
 
 use proc_macro::TokenStream;
 use proc_macro2;
 use quote::quote;
 use syn::{parse_macro_input, DeriveInput};
 
-#[proc_macro_derive(Attributes, attributes(attribute_name))]
+/// Implements `Attributes` for a struct, with one observed attribute per field.
+///
+/// The attribute name defaults to the field name. Use `#[attributes(rename_all = "kebab-case")]`
+/// (or `"snake_case"`, `"lowercase"`) and/or `#[attributes(prefix = "data-")]` on the struct to
+/// derive names from the field names, or `#[attribute_name = "..."]` on a field to set its name
+/// explicitly.
+///
+/// Also generates a struct that records which attributes changed, named after the struct with a
+/// `Diff` suffix; use `#[attributes(diff = "...")]` to choose another name (e.g. if the default
+/// name is already taken).
+#[proc_macro_derive(Attributes, attributes(attributes, attribute_name, attribute))]
 pub fn derive_attributes(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
@@ -15,6 +28,36 @@ pub fn derive_attributes(input: TokenStream) -> TokenStream {
         .into()
 }
 
+/// Implements `Attribute` for an enum without fields.
+///
+/// Attribute values are matched case-insensitively against the kebab-cased variant names, or
+/// against the value given with `#[attribute_value = "..."]`. A variant marked
+/// `#[attribute_value(fallback)]` is used when the attribute is absent or has an unknown value;
+/// without a fallback variant, an unknown value is a parse error.
+///
+/// Also implements `Display`, which writes the attribute value of the variant (e.g. to set the
+/// attribute with `Host::set_attribute`).
+#[proc_macro_derive(AttributeValue, attributes(attribute_value))]
+pub fn derive_attribute_value(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    attribute_value::expand_derive_attribute_value(&input)
+        .unwrap_or_else(compile_error)
+        .into()
+}
+
+/// Builds a `VDom` from JSX-like markup.
+///
+/// Expands to the equivalent `ElementBuilder` and `ChildKnownElementExt` calls. Attributes
+/// prefixed with `on:` bind event sinks; attributes prefixed with `xlink:` or `xml:` (e.g.
+/// `xlink:href`) are set in the XLink or XML namespace.
+#[proc_macro]
+pub fn view(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as view::View);
+
+    view::expand_view(&input).into()
+}
+
 fn compile_error(message: String) -> proc_macro2::TokenStream {
     quote! {
         compile_error!(#message);
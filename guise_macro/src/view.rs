@@ -0,0 +1,866 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, quote_spanned};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::{braced, Error, Expr, Ident, LitStr, Pat, Token};
+
+/// The tag names for which `ChildKnownElementExt` provides a `child_[tag name]` method.
+const KNOWN_ELEMENTS: &[&str] = &[
+    "base",
+    "head",
+    "link",
+    "meta",
+    "style",
+    "title",
+    "body",
+    "address",
+    "article",
+    "aside",
+    "footer",
+    "header",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "main",
+    "nav",
+    "section",
+    "blockquote",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "hr",
+    "li",
+    "menu",
+    "ol",
+    "p",
+    "pre",
+    "ul",
+    "a",
+    "abbr",
+    "b",
+    "bdi",
+    "bdo",
+    "br",
+    "cite",
+    "code",
+    "data",
+    "dfn",
+    "em",
+    "i",
+    "kbd",
+    "mark",
+    "q",
+    "rp",
+    "rt",
+    "ruby",
+    "s",
+    "samp",
+    "small",
+    "span",
+    "strong",
+    "sub",
+    "time",
+    "u",
+    "var",
+    "wbr",
+    "area",
+    "audio",
+    "img",
+    "map",
+    "track",
+    "video",
+    "embed",
+    "iframe",
+    "object",
+    "picture",
+    "source",
+    "del",
+    "ins",
+    "caption",
+    "colgroup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "button",
+    "datalist",
+    "fieldset",
+    "form",
+    "input",
+    "label",
+    "legend",
+    "meter",
+    "optgroup",
+    "option",
+    "output",
+    "progress",
+    "select",
+    "textarea",
+    "details",
+    "dialog",
+    "summary",
+    "slot",
+    "template",
+];
+
+/// The SVG elements that may be used inside an `<svg>` element.
+const SVG_ELEMENTS: &[&str] = &[
+    "a",
+    "circle",
+    "clipPath",
+    "defs",
+    "desc",
+    "ellipse",
+    "foreignObject",
+    "g",
+    "image",
+    "line",
+    "linearGradient",
+    "marker",
+    "mask",
+    "path",
+    "pattern",
+    "polygon",
+    "polyline",
+    "radialGradient",
+    "rect",
+    "stop",
+    "svg",
+    "symbol",
+    "text",
+    "title",
+    "tspan",
+    "use",
+];
+
+/// The attributes for which `GlobalAttrExt` provides an `attr_[attribute name]` method, which is
+/// available on every element builder.
+const GLOBAL_ATTRIBUTES: &[&str] = &[
+    "accesskey",
+    "class",
+    "contenteditable",
+    "contextmenu",
+    "dir",
+    "draggable",
+    "hidden",
+    "id",
+    "itemprop",
+    "lang",
+    "role",
+    "slot",
+    "spellcheck",
+    "style",
+    "tabindex",
+    "title",
+    "translate",
+];
+
+/// Boolean attributes; if such an attribute is given an expression as its value, the attribute is
+/// only set if the expression evaluates to `true`.
+const BOOLEAN_ATTRIBUTES: &[&str] = &[
+    "allowfullscreen",
+    "async",
+    "autofocus",
+    "autoplay",
+    "checked",
+    "controls",
+    "default",
+    "defer",
+    "disabled",
+    "formnovalidate",
+    "hidden",
+    "ismap",
+    "loop",
+    "multiple",
+    "muted",
+    "novalidate",
+    "open",
+    "readonly",
+    "required",
+    "reversed",
+    "selected",
+];
+
+/// DOM event names for which the `SinkUIEventExt` method name is not simply `sink_[event name]`.
+const EVENT_ALIASES: &[(&str, &str)] = &[
+    ("beforeinput", "before_input"),
+    ("focusin", "focus_in"),
+    ("focusout", "focus_out"),
+    ("dblclick", "dbl_click"),
+    ("auxclick", "aux_click"),
+    ("contextmenu", "context_menu"),
+    ("pointercancel", "pointer_cancel"),
+    ("pointerdown", "pointer_down"),
+    ("pointermove", "pointer_move"),
+    ("pointerup", "pointer_up"),
+    ("pointerout", "pointer_out"),
+    ("pointerover", "pointer_over"),
+    ("pointerenter", "pointer_enter"),
+    ("pointerleave", "pointer_leave"),
+    ("gotpointercapture", "got_pointer_capture"),
+    ("lostpointercapture", "lost_pointer_capture"),
+    ("dragend", "drag_end"),
+    ("dragenter", "drag_enter"),
+    ("dragleave", "drag_leave"),
+    ("dragover", "drag_over"),
+    ("dragstart", "drag_start"),
+    ("keydown", "key_down"),
+    ("keyup", "key_up"),
+];
+
+pub struct View {
+    children: Vec<Child>,
+}
+
+impl Parse for View {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let children = parse_children(input)?;
+
+        if !input.is_empty() {
+            return Err(input.error("unexpected closing tag"));
+        }
+
+        Ok(View { children })
+    }
+}
+
+enum Child {
+    Text(LitStr),
+    Interpolation(Expr),
+    Element(Element),
+    Fragment(Fragment),
+    If(If),
+    For(For),
+}
+
+struct If {
+    condition: Expr,
+    then_branch: Vec<Child>,
+    else_branch: Option<Else>,
+}
+
+enum Else {
+    If(Box<If>),
+    Block(Vec<Child>),
+}
+
+struct For {
+    pat: Pat,
+    expr: Expr,
+    body: Vec<Child>,
+}
+
+struct Element {
+    tag_name: DashedName,
+    attributes: Vec<Attribute>,
+    children: Vec<Child>,
+}
+
+/// A group of children without a wrapping element, e.g. `<>"a" "b"</>`.
+struct Fragment {
+    children: Vec<Child>,
+}
+
+/// A name that may consist of multiple dash-separated identifiers, e.g. `todo-item`.
+struct DashedName {
+    value: String,
+    span: Span,
+}
+
+impl DashedName {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let first = Ident::parse_any(input)?;
+        let span = first.span();
+        let mut value = first.to_string();
+
+        while input.peek(Token![-]) {
+            input.parse::<Token![-]>()?;
+
+            value.push('-');
+            value.push_str(&Ident::parse_any(input)?.to_string());
+        }
+
+        Ok(DashedName { value, span })
+    }
+}
+
+enum AttributeKind {
+    Key,
+    Ref,
+    Event,
+    Named,
+    /// An attribute in the XLink or XML namespace, e.g. `xlink:href` or `xml:lang`; holds the
+    /// name of the `Namespace` variant.
+    Namespaced(&'static str),
+}
+
+enum AttributeValue {
+    None,
+    Literal(LitStr),
+    Expr(Box<Expr>),
+}
+
+struct Attribute {
+    kind: AttributeKind,
+    name: DashedName,
+    value: AttributeValue,
+}
+
+impl Parse for Attribute {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut kind = AttributeKind::Named;
+        let mut prefix = None;
+
+        if input.peek(Ident::peek_any) && input.peek2(Token![:]) {
+            let namespace = Ident::parse_any(input)?;
+
+            kind = if namespace == "on" {
+                AttributeKind::Event
+            } else if namespace == "xlink" {
+                AttributeKind::Namespaced("XLink")
+            } else if namespace == "xml" {
+                AttributeKind::Namespaced("Xml")
+            } else {
+                return Err(Error::new(
+                    namespace.span(),
+                    format!(
+                        "unknown attribute namespace `{}`; expected `on`, `xlink` or `xml`",
+                        namespace
+                    ),
+                ));
+            };
+
+            input.parse::<Token![:]>()?;
+
+            prefix = Some(namespace);
+        }
+
+        let mut name = DashedName::parse(input)?;
+
+        // Namespaced attributes are set with their qualified name.
+        if let (AttributeKind::Namespaced(_), Some(prefix)) = (&kind, prefix) {
+            name.value = format!("{}:{}", prefix, name.value);
+        }
+
+        if let AttributeKind::Named = kind {
+            if name.value == "key" {
+                kind = AttributeKind::Key;
+            } else if name.value == "ref" {
+                kind = AttributeKind::Ref;
+            }
+        }
+
+        let value = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+
+            if input.peek(LitStr) {
+                AttributeValue::Literal(input.parse()?)
+            } else if input.peek(syn::token::Brace) {
+                let content;
+
+                braced!(content in input);
+
+                AttributeValue::Expr(Box::new(content.parse()?))
+            } else {
+                return Err(input.error("expected a string literal or a `{...}` expression"));
+            }
+        } else {
+            AttributeValue::None
+        };
+
+        match (&kind, &value) {
+            (AttributeKind::Key, AttributeValue::None) => {
+                Err(Error::new(name.span, "`key` requires a value"))
+            }
+            (AttributeKind::Ref, AttributeValue::Expr(_)) => Ok(()),
+            (AttributeKind::Ref, _) => {
+                Err(Error::new(name.span, "`ref` requires a `{...}` expression"))
+            }
+            (AttributeKind::Event, AttributeValue::Expr(_)) => Ok(()),
+            (AttributeKind::Event, _) => Err(Error::new(
+                name.span,
+                "event bindings require a `{...}` expression that evaluates to a sink",
+            )),
+            _ => Ok(()),
+        }?;
+
+        Ok(Attribute { kind, name, value })
+    }
+}
+
+impl Parse for Element {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<Token![<]>()?;
+
+        let tag_name = DashedName::parse(input)?;
+        let mut attributes = Vec::new();
+
+        loop {
+            if input.peek(Token![/]) {
+                input.parse::<Token![/]>()?;
+                input.parse::<Token![>]>()?;
+
+                return Ok(Element {
+                    tag_name,
+                    attributes,
+                    children: Vec::new(),
+                });
+            }
+
+            if input.peek(Token![>]) {
+                input.parse::<Token![>]>()?;
+
+                break;
+            }
+
+            attributes.push(input.parse()?);
+        }
+
+        let children = parse_children(input)?;
+
+        if input.is_empty() {
+            return Err(Error::new(
+                tag_name.span,
+                format!("unclosed element `<{}>`", tag_name.value),
+            ));
+        }
+
+        input.parse::<Token![<]>()?;
+        input.parse::<Token![/]>()?;
+
+        let closing_name = DashedName::parse(input)?;
+
+        if closing_name.value != tag_name.value {
+            return Err(Error::new(
+                closing_name.span,
+                format!(
+                    "closing tag `</{}>` does not match opening tag `<{}>`",
+                    closing_name.value, tag_name.value
+                ),
+            ));
+        }
+
+        input.parse::<Token![>]>()?;
+
+        Ok(Element {
+            tag_name,
+            attributes,
+            children,
+        })
+    }
+}
+
+impl Parse for Fragment {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let open = input.parse::<Token![<]>()?;
+
+        input.parse::<Token![>]>()?;
+
+        let children = parse_children(input)?;
+
+        if input.is_empty() {
+            return Err(Error::new(open.span, "unclosed fragment `<>`"));
+        }
+
+        input.parse::<Token![<]>()?;
+        input.parse::<Token![/]>()?;
+
+        if !input.peek(Token![>]) {
+            return Err(input.error("expected `</>` to close fragment `<>`"));
+        }
+
+        input.parse::<Token![>]>()?;
+
+        Ok(Fragment { children })
+    }
+}
+
+impl Parse for If {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<Token![if]>()?;
+
+        let condition = Expr::parse_without_eager_brace(input)?;
+        let then_branch = parse_block(input)?;
+
+        let else_branch = if input.peek(Token![else]) {
+            input.parse::<Token![else]>()?;
+
+            if input.peek(Token![if]) {
+                Some(Else::If(Box::new(input.parse()?)))
+            } else {
+                Some(Else::Block(parse_block(input)?))
+            }
+        } else {
+            None
+        };
+
+        Ok(If {
+            condition,
+            then_branch,
+            else_branch,
+        })
+    }
+}
+
+impl Parse for For {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<Token![for]>()?;
+
+        let pat = input.parse()?;
+
+        input.parse::<Token![in]>()?;
+
+        let expr = Expr::parse_without_eager_brace(input)?;
+        let body = parse_block(input)?;
+
+        Ok(For { pat, expr, body })
+    }
+}
+
+fn parse_block(input: ParseStream) -> syn::Result<Vec<Child>> {
+    let content;
+
+    braced!(content in input);
+
+    let children = parse_children(&content)?;
+
+    if !content.is_empty() {
+        return Err(content.error("unexpected closing tag"));
+    }
+
+    Ok(children)
+}
+
+fn parse_children(input: ParseStream) -> syn::Result<Vec<Child>> {
+    let mut children = Vec::new();
+
+    while !(input.is_empty() || input.peek(Token![<]) && input.peek2(Token![/])) {
+        let child = if input.peek(Token![<]) && input.peek2(Token![>]) {
+            Child::Fragment(input.parse()?)
+        } else if input.peek(Token![<]) {
+            Child::Element(input.parse()?)
+        } else if input.peek(LitStr) {
+            Child::Text(input.parse()?)
+        } else if input.peek(syn::token::Brace) {
+            let content;
+
+            braced!(content in input);
+
+            Child::Interpolation(content.parse()?)
+        } else if input.peek(Token![if]) {
+            Child::If(input.parse()?)
+        } else if input.peek(Token![for]) {
+            Child::For(input.parse()?)
+        } else {
+            return Err(input.error(
+                "expected an element, a string literal, a `{...}` expression, `if` or `for`",
+            ));
+        };
+
+        children.push(child);
+    }
+
+    Ok(children)
+}
+
+pub fn expand_view(view: &View) -> TokenStream {
+    let mod_path = quote!(guise);
+    let target = Ident::new("__guise_vdom", Span::call_site());
+
+    if view.children.is_empty() {
+        return quote!(#mod_path::VDom::new());
+    }
+
+    let children = match expand_children(&target, &view.children, false, &mod_path) {
+        Ok(children) => children,
+        Err(err) => return err.to_compile_error(),
+    };
+
+    quote! {
+        {
+            #[allow(unused_imports)]
+            use #mod_path::vdom_builder_ext::*;
+
+            let mut #target = #mod_path::VDom::new();
+
+            #children
+
+            #target
+        }
+    }
+}
+
+fn expand_children(
+    target: &Ident,
+    children: &[Child],
+    in_svg: bool,
+    mod_path: &TokenStream,
+) -> syn::Result<TokenStream> {
+    let mut output = TokenStream::new();
+
+    for child in children {
+        output.extend(expand_child(target, child, in_svg, mod_path)?);
+    }
+
+    Ok(output)
+}
+
+fn expand_child(
+    target: &Ident,
+    child: &Child,
+    in_svg: bool,
+    mod_path: &TokenStream,
+) -> syn::Result<TokenStream> {
+    let expanded = match child {
+        Child::Text(text) => quote!(#target.text(#text);),
+        Child::Interpolation(expr) => {
+            quote_spanned!(expr.span()=>
+                #target.text(&::std::string::ToString::to_string(&(#expr)));
+            )
+        }
+        Child::Element(element) => expand_element(target, element, in_svg, mod_path)?,
+        Child::Fragment(fragment) => {
+            let builder = Ident::new("__guise_f", Span::call_site());
+            let body = expand_children(&builder, &fragment.children, in_svg, mod_path)?;
+
+            if body.is_empty() {
+                quote!(#target.fragment(|_| {});)
+            } else {
+                quote!(#target.fragment(|mut #builder| { #body });)
+            }
+        }
+        Child::If(if_child) => expand_if(target, if_child, in_svg, mod_path)?,
+        Child::For(For { pat, expr, body }) => {
+            let body = expand_children(target, body, in_svg, mod_path)?;
+
+            quote! {
+                for #pat in #expr {
+                    #body
+                }
+            }
+        }
+    };
+
+    Ok(expanded)
+}
+
+fn expand_if(
+    target: &Ident,
+    if_child: &If,
+    in_svg: bool,
+    mod_path: &TokenStream,
+) -> syn::Result<TokenStream> {
+    let condition = &if_child.condition;
+    let then_branch = expand_children(target, &if_child.then_branch, in_svg, mod_path)?;
+
+    let else_branch = match &if_child.else_branch {
+        Some(Else::If(else_if)) => {
+            let else_if = expand_if(target, else_if, in_svg, mod_path)?;
+
+            quote!(else #else_if)
+        }
+        Some(Else::Block(children)) => {
+            let children = expand_children(target, children, in_svg, mod_path)?;
+
+            quote!(else { #children })
+        }
+        None => TokenStream::new(),
+    };
+
+    Ok(quote! {
+        if #condition {
+            #then_branch
+        } #else_branch
+    })
+}
+
+fn expand_element(
+    target: &Ident,
+    element: &Element,
+    in_svg: bool,
+    mod_path: &TokenStream,
+) -> syn::Result<TokenStream> {
+    let tag_name = &element.tag_name.value;
+    let span = element.tag_name.span;
+    let builder = Ident::new("__guise_e", Span::call_site());
+
+    let is = element
+        .attributes
+        .iter()
+        .find(|a| matches!(a.kind, AttributeKind::Named) && a.name.value == "is");
+
+    // SVG elements are created with `child`, which places them in the SVG namespace based on
+    // their parent.
+    let is_svg = in_svg || tag_name == "svg";
+
+    if is_svg && !SVG_ELEMENTS.contains(&tag_name.as_str()) {
+        return Err(Error::new(
+            span,
+            format!("unknown SVG element `<{}>`", tag_name),
+        ));
+    }
+
+    let is_custom = !is_svg && tag_name.contains('-');
+    let is_known = KNOWN_ELEMENTS.contains(&tag_name.as_str());
+
+    if !is_svg && !is_custom && !is_known {
+        return Err(Error::new(
+            span,
+            format!("unknown element `<{}>`", tag_name),
+        ));
+    }
+
+    // Customized built-in elements, autonomous custom elements and SVG elements get a dynamically
+    // typed element builder, for which only the global attributes have typed attribute methods.
+    let dynamic = is_svg || is_custom || is.is_some();
+
+    let mut body = TokenStream::new();
+
+    for attribute in element.attributes.iter() {
+        if let AttributeKind::Named = attribute.kind {
+            if attribute.name.value == "is" {
+                continue;
+            }
+        }
+
+        body.extend(expand_attribute(&builder, attribute, dynamic, mod_path));
+    }
+
+    let children_in_svg = is_svg && tag_name != "foreignObject";
+
+    body.extend(expand_children(
+        &builder,
+        &element.children,
+        children_in_svg,
+        mod_path,
+    )?);
+
+    let closure = if body.is_empty() {
+        quote!(|_| {})
+    } else {
+        quote!(|mut #builder| { #body })
+    };
+
+    if let Some(is) = is {
+        let is = if let AttributeValue::Literal(is) = &is.value {
+            is
+        } else {
+            return Err(Error::new(
+                is.name.span,
+                "`is` requires a string literal value",
+            ));
+        };
+
+        Ok(quote_spanned! {span=>
+            #target.child_customized(
+                #mod_path::name!(#tag_name),
+                #mod_path::custom_element_name!(#is),
+                #closure
+            );
+        })
+    } else if is_svg || is_custom {
+        Ok(quote_spanned! {span=>
+            #target.child(#mod_path::name!(#tag_name), #closure);
+        })
+    } else {
+        let method = format_ident!("child_{}", tag_name, span = span);
+
+        Ok(quote_spanned! {span=>
+            #target.#method(#closure);
+        })
+    }
+}
+
+fn expand_attribute(
+    builder: &Ident,
+    attribute: &Attribute,
+    dynamic: bool,
+    mod_path: &TokenStream,
+) -> TokenStream {
+    let name = &attribute.name.value;
+    let span = attribute.name.span;
+
+    let value = match &attribute.value {
+        AttributeValue::None => None,
+        AttributeValue::Literal(lit) => Some(quote!(#lit)),
+        AttributeValue::Expr(expr) => Some(quote_spanned!(expr.span()=>
+            ::core::convert::AsRef::<str>::as_ref(&(#expr))
+        )),
+    };
+
+    match attribute.kind {
+        AttributeKind::Key => quote_spanned!(span=> #builder.key(#value);),
+        AttributeKind::Ref => {
+            let AttributeValue::Expr(expr) = &attribute.value else {
+                unreachable!()
+            };
+
+            quote_spanned!(span=> #builder.element_ref(#expr);)
+        }
+        AttributeKind::Event => {
+            let AttributeValue::Expr(expr) = &attribute.value else {
+                unreachable!()
+            };
+
+            let event_name = EVENT_ALIASES
+                .iter()
+                .find(|(alias, _)| alias == name)
+                .map(|(_, method_name)| *method_name)
+                .unwrap_or(name.as_str())
+                .replace('-', "_");
+            let method = format_ident!("sink_{}", event_name, span = span);
+
+            quote_spanned!(span=> #builder.#method(#expr);)
+        }
+        AttributeKind::Namespaced(namespace) => {
+            let namespace = format_ident!("{}", namespace, span = span);
+            let value = value.unwrap_or_else(|| quote!(""));
+
+            quote_spanned!(span=>
+                #builder.attr_ns(#mod_path::Namespace::#namespace, #mod_path::name!(#name), #value);
+            )
+        }
+        AttributeKind::Named => {
+            let is_boolean = BOOLEAN_ATTRIBUTES.contains(&name.as_str());
+            let generic = name.starts_with("data-")
+                || name.starts_with("aria-")
+                || (dynamic && !GLOBAL_ATTRIBUTES.contains(&name.as_str()));
+
+            let call = if generic {
+                match (&value, is_boolean) {
+                    (Some(value), false) => {
+                        quote_spanned!(span=> #builder.attr(#mod_path::name!(#name), #value);)
+                    }
+                    _ => quote_spanned!(span=> #builder.boolean_attr(#mod_path::name!(#name));),
+                }
+            } else {
+                let method = format_ident!("attr_{}", name.replace('-', "_"), span = span);
+
+                match (&value, is_boolean) {
+                    (Some(value), false) => quote_spanned!(span=> #builder.#method(#value);),
+                    _ => quote_spanned!(span=> #builder.#method();),
+                }
+            };
+
+            // A boolean attribute with an expression value is set conditionally.
+            if let (AttributeValue::Expr(expr), true) = (&attribute.value, is_boolean) {
+                quote! {
+                    if #expr {
+                        #call
+                    }
+                }
+            } else {
+                call
+            }
+        }
+    }
+}
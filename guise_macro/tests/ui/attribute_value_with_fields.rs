@@ -0,0 +1,9 @@
+use guise_macro::AttributeValue;
+
+#[derive(AttributeValue)]
+enum Variant {
+    Primary,
+    Secondary(u32),
+}
+
+fn main() {}
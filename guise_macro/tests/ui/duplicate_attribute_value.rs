@@ -0,0 +1,10 @@
+use guise_macro::AttributeValue;
+
+#[derive(AttributeValue)]
+enum Variant {
+    Primary,
+    #[attribute_value = "PRIMARY"]
+    Main,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use guise_macro::Attributes;
+
+#[derive(Clone, Default, Attributes)]
+#[attributes(rename_all = "kebab-case")]
+struct CounterAttributes {
+    initial_count: u32,
+    #[attribute_name = "initial-count"]
+    start: u32,
+}
+
+fn main() {}
@@ -0,0 +1,5 @@
+use guise_macro::view;
+
+fn main() {
+    view! { <div on:click="a"></div> };
+}
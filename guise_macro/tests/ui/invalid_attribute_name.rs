@@ -0,0 +1,9 @@
+use guise_macro::Attributes;
+
+#[derive(Clone, Default, Attributes)]
+struct CounterAttributes {
+    #[attribute_name = "initial count"]
+    initial_count: u32,
+}
+
+fn main() {}
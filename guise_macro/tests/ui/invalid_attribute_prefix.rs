@@ -0,0 +1,9 @@
+use guise_macro::Attributes;
+
+#[derive(Clone, Default, Attributes)]
+#[attributes(rename_all = "kebab-case", prefix = "data=")]
+struct CounterAttributes {
+    initial_count: u32,
+}
+
+fn main() {}